@@ -1,6 +1,7 @@
+use crate::error::*;
+use crate::util::*;
 use byteorder::WriteBytesExt;
 use nom::{self, number::complete::*, *};
-use std;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ServerListType {
@@ -49,10 +50,10 @@ impl ClientGetListData {
     }
 }
 
-named!(pub parse_client_get_list<&[u8], ClientGetListData>,
+named!(pub parse_client_get_list<&[u8], ClientGetListData, Error>,
     do_parse!(
         master_server_version: le_u8 >>
-        request_type: map_opt!(le_u8, ServerListType::from_num) >>
+        request_type: call!(typed_u8, "server list request", ServerListType::from_num) >>
         (ClientGetListData {
             master_server_version,
             request_type,
@@ -0,0 +1,111 @@
+use crate::error::*;
+use crate::limits::*;
+use crate::server_response::{newgrf_entry, NewGRFHash, V4Data};
+use crate::util::*;
+
+use byteorder::{LittleEndian, WriteBytesExt};
+use nom::{self, number::complete::*, *};
+use std::collections::BTreeMap;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClientGetNewGRFsData {
+    pub newgrfs: Vec<(u32, NewGRFHash)>,
+}
+
+impl<'a> From<&'a V4Data> for ClientGetNewGRFsData {
+    /// Request names for all NewGRFs advertised by the server
+    fn from(v: &'a V4Data) -> Self {
+        Self {
+            newgrfs: v
+                .active_newgrf
+                .clone()
+                .into_iter()
+                .collect::<BTreeMap<_, _>>()
+                .into_iter()
+                .collect(),
+        }
+    }
+}
+
+impl ByteWriter for ClientGetNewGRFsData {
+    fn write_pkt(&self, buf: &mut Vec<u8>) -> std::io::Result<()> {
+        write_newgrf_count(self.newgrfs.len(), buf)?;
+        for (id, hash) in self.newgrfs.iter() {
+            buf.write_u32::<LittleEndian>(*id)?;
+            buf.extend_from_slice(&hash.0);
+        }
+
+        Ok(())
+    }
+}
+
+named!(pub parse_client_get_newgrfs<&[u8], ClientGetNewGRFsData, Error>,
+    do_parse!(
+        newgrf_num: le_u8 >>
+        newgrfs: count!(newgrf_entry, newgrf_num as usize) >>
+        (ClientGetNewGRFsData { newgrfs })
+    )
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use hex_literal::hex;
+
+    fn fixtures() -> (Vec<u8>, ClientGetNewGRFsData) {
+        let b = hex!(
+            "
+            02
+            444E0700 48B3F9E4FD0DF2A72B5F44D3C8A2F4A0
+            4D470404 6A4B87A55C5D18F5D6F8C7AE3C7E1F07
+        "
+        )
+        .to_vec();
+
+        let data = ClientGetNewGRFsData {
+            newgrfs: vec![
+                (
+                    0x00074e44,
+                    NewGRFHash(hex!("48b3f9e4fd0df2a72b5f44d3c8a2f4a0")),
+                ),
+                (
+                    0x0404474d,
+                    NewGRFHash(hex!("6a4b87a55c5d18f5d6f8c7ae3c7e1f07")),
+                ),
+            ],
+        };
+
+        (b, data)
+    }
+
+    #[test]
+    fn test_parse_client_get_newgrfs() {
+        let (input, expectation) = fixtures();
+
+        let result = parse_client_get_newgrfs(&input).unwrap();
+
+        assert_eq!(expectation, result.1);
+    }
+
+    #[test]
+    fn test_write_client_get_newgrfs() {
+        let (expectation, input) = fixtures();
+
+        let mut result = Vec::new();
+        input.write_pkt(&mut result).unwrap();
+
+        assert_eq!(expectation, result);
+    }
+
+    #[test]
+    fn test_client_get_newgrfs_from_v4() {
+        let (_, expectation) = fixtures();
+
+        let v4 = V4Data {
+            active_newgrf: expectation.newgrfs.iter().rev().cloned().collect(),
+        };
+
+        assert_eq!(ClientGetNewGRFsData::from(&v4), expectation);
+    }
+}
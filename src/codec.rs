@@ -0,0 +1,119 @@
+use crate::error::*;
+use crate::Packet;
+
+use bytes::{BufMut, BytesMut};
+use std::fmt;
+use std::io;
+use tokio_util::codec::{Decoder, Encoder};
+
+/// Failure to decode or encode a datagram
+#[derive(Debug)]
+pub enum CodecError {
+    Io(io::Error),
+    /// The datagram could not be parsed. The stream stays usable and yields the next datagram.
+    Parse(Error),
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            CodecError::Io(ref e) => write!(fmt, "I/O error: {}", e),
+            CodecError::Parse(ref e) => write!(fmt, "invalid packet: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CodecError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            CodecError::Io(ref e) => Some(e),
+            CodecError::Parse(ref e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for CodecError {
+    fn from(e: io::Error) -> Self {
+        CodecError::Io(e)
+    }
+}
+
+/// Codec for use with `tokio_util::udp::UdpFramed`, one packet per datagram
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PacketCodec {
+    lenient: bool,
+}
+
+impl PacketCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode with `Packet::from_incoming_bytes_lenient` instead of `Packet::from_incoming_bytes`
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+}
+
+impl Decoder for PacketCodec {
+    type Item = Packet;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Packet>, CodecError> {
+        if src.is_empty() {
+            return Ok(None);
+        }
+
+        // Consume the whole datagram up front, so that a parse error does not leave it behind
+        // to be decoded again
+        let datagram = src.split();
+        let packet = if self.lenient {
+            Packet::from_incoming_bytes_lenient(&datagram)
+        } else {
+            Packet::from_incoming_bytes(&datagram)
+        };
+
+        packet.map(Some).map_err(CodecError::Parse)
+    }
+}
+
+impl Encoder<Packet> for PacketCodec {
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Packet, dst: &mut BytesMut) -> Result<(), CodecError> {
+        dst.put_slice(&item.to_bytes()?);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use futures_util::{SinkExt, StreamExt};
+    use tokio::net::UdpSocket;
+    use tokio_util::udp::UdpFramed;
+
+    #[tokio::test]
+    async fn test_packet_codec() {
+        let a = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let b = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let b_addr = b.local_addr().unwrap();
+
+        // Garbage first, which must not end the stream
+        a.send_to(&[3, 0, 42], b_addr).await.unwrap();
+
+        let mut a = UdpFramed::new(a, PacketCodec::new());
+        let mut b = UdpFramed::new(b, PacketCodec::new());
+        a.send((Packet::ClientFindServer, b_addr)).await.unwrap();
+
+        match b.next().await.unwrap() {
+            Err(CodecError::Parse(Error::UnknownType { value: 42, .. })) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+        let (packet, _) = b.next().await.unwrap().unwrap();
+        assert_eq!(packet, Packet::ClientFindServer);
+    }
+}
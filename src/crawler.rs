@@ -0,0 +1,350 @@
+use crate::client_get_list::*;
+use crate::master_response_list::*;
+use crate::query_client::QueryError;
+use crate::registration::NETWORK_MASTER_SERVER_VERSION;
+use crate::server_detail_info::*;
+use crate::server_response::*;
+use crate::util::*;
+use crate::{Packet, PacketType};
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+/// Results of a crawl
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CrawlSnapshot {
+    pub servers: HashMap<SocketAddr, ServerResponse>,
+    /// Only filled if detail queries are enabled and the server answered them
+    pub details: HashMap<SocketAddr, ServerDetailInfo>,
+    /// Servers that did not answer `ClientFindServer`, in the order they were given. This
+    /// includes servers the query could not be sent to, e.g. because their address family does
+    /// not match the crawler's socket.
+    pub unreachable: Vec<SocketAddr>,
+}
+
+/// Query waiting to be sent or answered
+#[derive(Clone, Copy, Debug)]
+struct Query {
+    server: SocketAddr,
+    reply: PacketType,
+    attempts: u32,
+}
+
+impl Query {
+    fn request(&self) -> Packet {
+        match self.reply {
+            PacketType::ServerDetailInfo => Packet::ClientDetailInfo,
+            _ => Packet::ClientFindServer,
+        }
+    }
+}
+
+/// Queries many servers at once over a single UDP socket
+#[derive(Debug)]
+pub struct Crawler {
+    socket: UdpSocket,
+    timeout: Duration,
+    retries: u32,
+    send_interval: Duration,
+    max_in_flight: usize,
+    detail_info: bool,
+}
+
+impl Crawler {
+    /// Bind to an ephemeral IPv4 port. Use `bind` to crawl IPv6 servers, which an IPv4 crawler
+    /// reports as unreachable.
+    pub fn new() -> io::Result<Self> {
+        Self::bind("0.0.0.0:0")
+    }
+
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        Ok(Self {
+            socket: UdpSocket::bind(addr)?,
+            timeout: Duration::from_secs(2),
+            retries: 2,
+            send_interval: Duration::from_millis(1),
+            max_in_flight: 64,
+            detail_info: true,
+        })
+    }
+
+    /// Time to wait for each server's reply before resending, defaults to 2 seconds
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Number of times a query is resent after a timeout, defaults to 2
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Minimum time between two sent packets, defaults to 1 millisecond
+    pub fn send_interval(mut self, send_interval: Duration) -> Self {
+        self.send_interval = send_interval;
+        self
+    }
+
+    /// Maximum number of unanswered queries at any time, defaults to 64
+    pub fn max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight.max(1);
+        self
+    }
+
+    /// Whether to also send `ClientDetailInfo` to every server, defaults to true
+    pub fn detail_info(mut self, detail_info: bool) -> Self {
+        self.detail_info = detail_info;
+        self
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Fetch the server list from a master server, merging all pages that arrive
+    pub fn fetch_server_list(
+        &self,
+        master: SocketAddr,
+        request_type: ServerListType,
+    ) -> Result<Vec<SocketAddr>, QueryError> {
+        let request = Packet::ClientGetList(ClientGetListData {
+            master_server_version: NETWORK_MASTER_SERVER_VERSION,
+            request_type,
+        })
+        .to_bytes()?;
+
+        let mut buf = [0; SEND_MTU];
+        for _ in 0..=self.retries {
+            self.socket.send_to(&request, master)?;
+
+            // The number of pages is not known up front, so wait until the master goes quiet
+            let mut aggregator = ServerListAggregator::new();
+            let mut deadline = Instant::now() + self.timeout;
+            loop {
+                let (len, from) = match recv_until(&self.socket, &mut buf, deadline)? {
+                    Some(v) => v,
+                    None if Instant::now() < deadline => continue,
+                    None => break,
+                };
+                if from != master {
+                    continue;
+                }
+                if let Ok(packet) = Packet::from_incoming_bytes(&buf[..len]) {
+                    if aggregator.add_packet(&packet) {
+                        deadline = Instant::now() + self.timeout;
+                    }
+                }
+            }
+
+            let lists = aggregator.into_lists();
+            if !lists.is_empty() {
+                return Ok(lists
+                    .iter()
+                    .flat_map(|list| list.deduped().socket_addrs())
+                    .collect());
+            }
+        }
+
+        Err(QueryError::Timeout)
+    }
+
+    /// Query every server, sending at most `max_in_flight` queries at once. A query that cannot
+    /// be sent is given up on without affecting the others.
+    pub fn crawl(&self, servers: &[SocketAddr]) -> io::Result<CrawlSnapshot> {
+        let mut seen = HashSet::new();
+        let servers: Vec<_> = servers
+            .iter()
+            .cloned()
+            .filter(|server| seen.insert(*server))
+            .collect();
+
+        let is_ipv4 = self.socket.local_addr()?.is_ipv4();
+        let mut queue: VecDeque<Query> = VecDeque::new();
+        for server in servers.iter().filter(|server| server.is_ipv4() == is_ipv4) {
+            queue.push_back(Query {
+                server: *server,
+                reply: PacketType::ServerResponse,
+                attempts: 0,
+            });
+            if self.detail_info {
+                queue.push_back(Query {
+                    server: *server,
+                    reply: PacketType::ServerDetailInfo,
+                    attempts: 0,
+                });
+            }
+        }
+
+        let mut snapshot = CrawlSnapshot::default();
+        let mut in_flight: HashMap<(SocketAddr, PacketType), (Query, Instant)> = HashMap::new();
+        let mut next_send = Instant::now();
+        let mut buf = [0; SEND_MTU];
+
+        while !queue.is_empty() || !in_flight.is_empty() {
+            let now = Instant::now();
+
+            // Resend or give up on queries whose reply is overdue
+            let overdue: Vec<_> = in_flight
+                .iter()
+                .filter(|(_, (_, sent_at))| now >= *sent_at + self.timeout)
+                .map(|(key, _)| *key)
+                .collect();
+            for key in overdue {
+                let (query, _) = in_flight.remove(&key).unwrap();
+                if query.attempts <= self.retries {
+                    queue.push_front(query);
+                }
+            }
+
+            if now >= next_send && in_flight.len() < self.max_in_flight {
+                if let Some(mut query) = queue.pop_front() {
+                    next_send = now + self.send_interval;
+                    // E.g. broadcast addresses on the list, which would fail on every attempt
+                    if self
+                        .socket
+                        .send_to(&query.request().to_bytes()?, query.server)
+                        .is_err()
+                    {
+                        continue;
+                    }
+                    query.attempts += 1;
+                    in_flight.insert((query.server, query.reply), (query, now));
+                    continue;
+                }
+            }
+
+            // Sleep until the next packet may be sent or the next query times out
+            let mut deadline = in_flight
+                .values()
+                .map(|(_, sent_at)| *sent_at + self.timeout)
+                .min()
+                .unwrap_or(next_send);
+            if !queue.is_empty() && in_flight.len() < self.max_in_flight {
+                deadline = deadline.min(next_send);
+            }
+
+            let (len, from) = match recv_until(&self.socket, &mut buf, deadline)? {
+                Some(v) => v,
+                None => continue,
+            };
+            let packet = match Packet::from_incoming_bytes(&buf[..len]) {
+                Ok(packet) => packet,
+                Err(_) => continue,
+            };
+            // Only accept replies to queries we sent, from the address we sent them to
+            if in_flight.remove(&(from, packet.pkt_type())).is_none() {
+                continue;
+            }
+            match packet {
+                Packet::ServerResponse(data) => {
+                    snapshot.servers.insert(from, data);
+                }
+                Packet::ServerDetailInfo(data) => {
+                    snapshot.details.insert(from, data);
+                }
+                _ => {}
+            }
+        }
+
+        snapshot.unreachable = servers
+            .into_iter()
+            .filter(|server| !snapshot.servers.contains_key(server))
+            .collect();
+
+        Ok(snapshot)
+    }
+
+    /// Fetch the server list from a master server and query every server on it
+    pub fn crawl_master(
+        &self,
+        master: SocketAddr,
+        request_type: ServerListType,
+    ) -> Result<CrawlSnapshot, QueryError> {
+        let servers = self.fetch_server_list(master, request_type)?;
+
+        Ok(self.crawl(&servers)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::StandIn;
+    use std::net::{Ipv4Addr, Ipv6Addr, SocketAddrV4, SocketAddrV6};
+    use std::thread;
+
+    /// Stand-in master server listing `servers`, one per page
+    fn stand_in_master(servers: Vec<SocketAddr>) -> SocketAddr {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = socket.local_addr().unwrap();
+
+        thread::spawn(move || {
+            let mut buf = [0; SEND_MTU];
+            let (_, from) = socket.recv_from(&mut buf).unwrap();
+            for server in servers {
+                let list = match server {
+                    SocketAddr::V4(v4) => ServerList::IPv4(vec![v4]),
+                    SocketAddr::V6(v6) => ServerList::IPv6(vec![v6]),
+                };
+                let pkt = Packet::MasterResponseList(list);
+                socket.send_to(&pkt.to_bytes().unwrap(), from).unwrap();
+            }
+        });
+
+        addr
+    }
+
+    #[test]
+    fn test_crawl_master() {
+        let alpha = StandIn::new("Alpha").spawn();
+        let beta = StandIn::new("Beta").ignore(1).spawn();
+        let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let silent_addr = silent.local_addr().unwrap();
+        let master = stand_in_master(vec![alpha, silent_addr, beta, alpha]);
+
+        let crawler = Crawler::bind("127.0.0.1:0")
+            .unwrap()
+            .timeout(Duration::from_millis(200))
+            .retries(1);
+        let snapshot = crawler.crawl_master(master, ServerListType::IPv4).unwrap();
+
+        assert_eq!(snapshot.servers.len(), 2);
+        assert_eq!(snapshot.servers[&alpha].server_name.to_str(), Ok("Alpha"));
+        assert_eq!(snapshot.servers[&beta].server_name.to_str(), Ok("Beta"));
+        assert!(snapshot.details.contains_key(&alpha));
+        assert_eq!(snapshot.unreachable, vec![silent_addr]);
+
+        let unused = SocketAddr::V4(SocketAddrV4::new([127, 0, 0, 1].into(), 9));
+        let snapshot = crawler
+            .detail_info(false)
+            .retries(0)
+            .crawl(&[unused])
+            .unwrap();
+        assert_eq!(snapshot.unreachable, vec![unused]);
+    }
+
+    #[test]
+    fn test_crawl_unsendable() {
+        let alpha = StandIn::new("Alpha").spawn();
+        let beta = StandIn::new("Beta").spawn();
+        let broadcast = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::BROADCAST, alpha.port()));
+        let ipv6 = SocketAddr::V6(SocketAddrV6::new(Ipv6Addr::LOCALHOST, alpha.port(), 0, 0));
+        let master = stand_in_master(vec![alpha, broadcast, ipv6, beta]);
+
+        let crawler = Crawler::bind("127.0.0.1:0")
+            .unwrap()
+            .timeout(Duration::from_millis(200))
+            .retries(1);
+        let snapshot = crawler
+            .crawl_master(master, ServerListType::Autodetect)
+            .unwrap();
+
+        assert_eq!(snapshot.servers.len(), 2);
+        assert!(snapshot.servers.contains_key(&alpha));
+        assert!(snapshot.servers.contains_key(&beta));
+        assert_eq!(snapshot.unreachable, vec![broadcast, ipv6]);
+    }
+}
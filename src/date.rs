@@ -0,0 +1,283 @@
+use crate::error::*;
+use crate::util::*;
+
+use byteorder::{LittleEndian, WriteBytesExt};
+use chrono::NaiveDate;
+use nom::{self, number::complete::*, *};
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::{Add, Sub};
+
+const DAYS_IN_YEAR: u64 = 365;
+/// Date of 1 January 1920, which older game info versions count from
+pub const DAYS_TILL_ORIGINAL_BASE_YEAR: u32 = 701_265;
+/// Cumulative number of days before each month in a non-leap year
+const DAYS_BEFORE_MONTH: [u32; 12] = [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334];
+
+/// Check whether the year is a leap year, the same way OpenTTD does
+pub fn is_leap_year(year: u32) -> bool {
+    year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400))
+}
+
+fn leap_years_till(year: u64) -> u64 {
+    if year == 0 {
+        0
+    } else {
+        (year - 1) / 4 - (year - 1) / 100 + (year - 1) / 400 + 1
+    }
+}
+
+/// Number of days from 1 January of year 0 until 1 January of the given year
+fn days_till(year: u64) -> u64 {
+    DAYS_IN_YEAR * year + leap_years_till(year)
+}
+
+fn days_in_month(year: u32, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// In-game date, counted in days since 1 January of year 0
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct OpenTTDDate(pub u32);
+
+impl OpenTTDDate {
+    /// Build a date from year, month (1-12) and day of month (1-31)
+    pub fn from_ymd(year: u32, month: u8, day: u8) -> Option<Self> {
+        if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+            return None;
+        }
+
+        let mut days = days_till(year.into()) + u64::from(DAYS_BEFORE_MONTH[month as usize - 1]);
+        if month > 2 && is_leap_year(year) {
+            days += 1;
+        }
+        days += u64::from(day) - 1;
+
+        if days > u64::from(u32::MAX) {
+            return None;
+        }
+
+        Some(OpenTTDDate(days as u32))
+    }
+
+    /// Split the date into year, month (1-12) and day of month (1-31)
+    pub fn ymd(&self) -> (u32, u8, u8) {
+        let date = u64::from(self.0);
+
+        // Start from an underestimate and move forward one year at a time
+        let mut year = date / (DAYS_IN_YEAR + 1);
+        while days_till(year + 1) <= date {
+            year += 1;
+        }
+        let year = year as u32;
+
+        let mut day_of_year = (date - days_till(year.into())) as u32;
+        if is_leap_year(year) && day_of_year >= DAYS_BEFORE_MONTH[2] {
+            if day_of_year == DAYS_BEFORE_MONTH[2] {
+                return (year, 2, 29);
+            }
+            day_of_year -= 1;
+        }
+
+        let month = DAYS_BEFORE_MONTH
+            .iter()
+            .rposition(|&v| v <= day_of_year)
+            .unwrap();
+
+        (
+            year,
+            month as u8 + 1,
+            (day_of_year - DAYS_BEFORE_MONTH[month]) as u8 + 1,
+        )
+    }
+
+    pub fn year(&self) -> u32 {
+        self.ymd().0
+    }
+
+    pub fn month(&self) -> u8 {
+        self.ymd().1
+    }
+
+    pub fn day(&self) -> u8 {
+        self.ymd().2
+    }
+
+    /// Convert into a calendar date, if it is within the range supported by chrono
+    pub fn to_naive_date(&self) -> Option<NaiveDate> {
+        let (year, month, day) = self.ymd();
+        if year > i32::MAX as u32 {
+            return None;
+        }
+
+        NaiveDate::from_ymd_opt(year as i32, month.into(), day.into())
+    }
+
+    /// Date from a day count since 1 January 1920, as sent by game info versions 1 and 2
+    pub fn from_original_days(days: u16) -> Self {
+        OpenTTDDate(DAYS_TILL_ORIGINAL_BASE_YEAR + u32::from(days))
+    }
+
+    /// Day count since 1 January 1920, as sent by game info versions 1 and 2. `None` if the
+    /// date lies outside 1920 to mid-2099, which those versions cannot represent.
+    pub fn to_original_days(&self) -> Option<u16> {
+        self.0
+            .checked_sub(DAYS_TILL_ORIGINAL_BASE_YEAR)
+            .and_then(|days| u16::try_from(days).ok())
+    }
+
+    /// Date `days` later, `None` past the last representable date
+    pub fn checked_add_days(&self, days: u32) -> Option<Self> {
+        self.0.checked_add(days).map(OpenTTDDate)
+    }
+
+    /// Date `days` earlier, `None` before 1 January of year 0
+    pub fn checked_sub_days(&self, days: u32) -> Option<Self> {
+        self.0.checked_sub(days).map(OpenTTDDate)
+    }
+}
+
+impl Add<u32> for OpenTTDDate {
+    type Output = OpenTTDDate;
+
+    /// Saturates at the last representable date, see `checked_add_days`
+    fn add(self, days: u32) -> OpenTTDDate {
+        OpenTTDDate(self.0.saturating_add(days))
+    }
+}
+
+impl Sub<u32> for OpenTTDDate {
+    type Output = OpenTTDDate;
+
+    /// Saturates at 1 January of year 0, see `checked_sub_days`
+    fn sub(self, days: u32) -> OpenTTDDate {
+        OpenTTDDate(self.0.saturating_sub(days))
+    }
+}
+
+impl Sub for OpenTTDDate {
+    type Output = i64;
+
+    /// Number of days between two dates
+    fn sub(self, other: OpenTTDDate) -> i64 {
+        i64::from(self.0) - i64::from(other.0)
+    }
+}
+
+impl fmt::Display for OpenTTDDate {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let (year, month, day) = self.ymd();
+        write!(fmt, "{}-{:02}-{:02}", year, month, day)
+    }
+}
+
+impl ByteWriter for OpenTTDDate {
+    fn write_pkt(&self, buf: &mut Vec<u8>) -> std::io::Result<()> {
+        buf.write_u32::<LittleEndian>(self.0)?;
+
+        Ok(())
+    }
+}
+
+named!(pub(crate) parse_date<&[u8], OpenTTDDate, Error>,
+    map!(le_u32, OpenTTDDate)
+);
+
+/// Write the date as a day count since 1 January 1920, failing if it does not fit
+pub(crate) fn write_original_date(date: OpenTTDDate, buf: &mut Vec<u8>) -> std::io::Result<()> {
+    let days = date.to_original_days().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("date {} cannot be sent as days since 1920", date),
+        )
+    })?;
+    buf.write_u16::<LittleEndian>(days)?;
+
+    Ok(())
+}
+
+named!(pub(crate) parse_original_date<&[u8], OpenTTDDate, Error>,
+    map!(le_u16, OpenTTDDate::from_original_days)
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_date_ymd() {
+        let base = OpenTTDDate::from_ymd(1920, 1, 1).unwrap();
+        assert_eq!(base, OpenTTDDate(DAYS_TILL_ORIGINAL_BASE_YEAR));
+        assert_eq!(OpenTTDDate(715875).ymd(), (1960, 1, 1));
+        assert_eq!(OpenTTDDate(0).ymd(), (0, 1, 1));
+        assert_eq!(OpenTTDDate(59).ymd(), (0, 2, 29));
+        assert_eq!(OpenTTDDate(366).ymd(), (1, 1, 1));
+
+        assert!(OpenTTDDate::from_ymd(2000, 2, 29).is_some());
+        assert!(OpenTTDDate::from_ymd(1900, 2, 29).is_none());
+        assert!(OpenTTDDate::from_ymd(1950, 13, 1).is_none());
+
+        let epoch = NaiveDate::from_ymd_opt(0, 1, 1).unwrap();
+        for days in (0..3_000_000).step_by(7) {
+            let date = OpenTTDDate(days);
+            let (year, month, day) = date.ymd();
+            assert_eq!(OpenTTDDate::from_ymd(year, month, day), Some(date));
+            assert_eq!(
+                date.to_naive_date().unwrap(),
+                epoch + chrono::Duration::days(days.into())
+            );
+        }
+    }
+
+    #[test]
+    fn test_date_arithmetic() {
+        let date = OpenTTDDate::from_ymd(1950, 12, 31).unwrap();
+        assert_eq!((date + 1).to_string(), "1951-01-01");
+        assert_eq!((date - 365).to_string(), "1949-12-31");
+        assert_eq!(date - OpenTTDDate::from_ymd(1950, 1, 1).unwrap(), 364);
+
+        assert_eq!(OpenTTDDate(0).checked_sub_days(1), None);
+        assert_eq!(OpenTTDDate(0) - 1, OpenTTDDate(0));
+        assert_eq!(OpenTTDDate(1).checked_sub_days(1), Some(OpenTTDDate(0)));
+        assert_eq!(OpenTTDDate(u32::MAX).checked_add_days(1), None);
+        assert_eq!(OpenTTDDate(u32::MAX) + 1, OpenTTDDate(u32::MAX));
+        assert_eq!(
+            OpenTTDDate(u32::MAX - 1).checked_add_days(1),
+            Some(OpenTTDDate(u32::MAX))
+        );
+    }
+
+    #[test]
+    fn test_original_date() {
+        let date = OpenTTDDate::from_ymd(1950, 1, 1).unwrap();
+        assert_eq!(date.to_original_days(), Some(10958));
+        assert_eq!(OpenTTDDate::from_original_days(10958), date);
+        assert_eq!(
+            OpenTTDDate::from_original_days(u16::MAX).to_string(),
+            "2099-06-05"
+        );
+        assert_eq!(
+            OpenTTDDate::from_ymd(1919, 12, 31)
+                .unwrap()
+                .to_original_days(),
+            None
+        );
+        assert_eq!(
+            OpenTTDDate::from_ymd(2100, 1, 1)
+                .unwrap()
+                .to_original_days(),
+            None
+        );
+
+        let mut buf = vec![];
+        write_original_date(date, &mut buf).unwrap();
+        assert_eq!(buf, vec![0xCE, 0x2A]);
+        assert_eq!(parse_original_date(&buf).unwrap().1, date);
+        assert!(write_original_date(OpenTTDDate(0), &mut buf).is_err());
+    }
+}
@@ -0,0 +1,63 @@
+use nom::error::{ErrorKind, ParseError};
+use std::fmt;
+
+/// Errors produced while decoding OpenTTD packets
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// A type tag (packet type, protocol version, list type...) has a value we do not know about
+    UnknownType { kind: &'static str, value: u8 },
+    /// The value is valid in some OpenTTD version, but this crate cannot decode it
+    UnsupportedType { kind: &'static str, value: u8 },
+    /// The input ended before the packet was complete
+    Truncated,
+    /// The length header does not match the amount of data
+    BadLength { declared: usize, actual: usize },
+    /// A string field is malformed
+    InvalidString,
+    /// The packet was decoded, but some bytes were left over
+    TrailingBytes(usize),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            Error::UnknownType { kind, value } => write!(fmt, "unknown {} type: {}", kind, value),
+            Error::UnsupportedType { kind, value } => {
+                write!(fmt, "unsupported {} type: {}", kind, value)
+            }
+            Error::Truncated => write!(fmt, "packet is truncated"),
+            Error::BadLength { declared, actual } => write!(
+                fmt,
+                "bad packet length: header says {}, got {}",
+                declared, actual
+            ),
+            Error::InvalidString => write!(fmt, "invalid string"),
+            Error::TrailingBytes(n) => write!(fmt, "{} trailing bytes after packet", n),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl<'a> ParseError<&'a [u8]> for Error {
+    /// Every primitive parser we use fails only when it runs out of input
+    fn from_error_kind(_: &'a [u8], _: ErrorKind) -> Self {
+        Error::Truncated
+    }
+
+    fn append(_: &'a [u8], _: ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+impl Error {
+    pub(crate) fn from_nom(e: nom::Err<Error>) -> Self {
+        match e {
+            nom::Err::Incomplete(_) => Error::Truncated,
+            nom::Err::Error(e) | nom::Err::Failure(e) => e,
+        }
+    }
+}
+
+/// Result of a parser in this crate
+pub type ParseResult<'a, T> = nom::IResult<&'a [u8], T, Error>;
@@ -0,0 +1,176 @@
+use crate::error::*;
+use crate::network_language::*;
+use crate::server_response::*;
+
+use std::collections::HashMap;
+use std::ffi::CString;
+
+/// Information that cannot be represented in the game info version a response was converted to
+#[derive(Clone, Debug, PartialEq)]
+pub enum DroppedData {
+    /// Company and spectator limits, sent since version 2
+    CompanyLimits(V2Data),
+    /// Game and start date outside 1920 to mid-2099, which versions 1 and 2 cannot send
+    Dates(V3Data),
+    /// Active NewGRFs, sent since version 4
+    NewGRFs(HashMap<u32, NewGRFHash>),
+    /// GameScript version and name, sent since version 5
+    GameScript(V5Data),
+    /// NewGRF names or lookup IDs, sent since version 6
+    NewGRFSerialisation(NewGRFSerialisation),
+    /// Ticks the game has been running, sent since version 7
+    TicksPlaying(u64),
+    /// Server language, not sent since version 6
+    ServerLang(NetworkLanguage),
+    /// Map name, not sent since version 6
+    MapName(CString),
+}
+
+/// Take a block for the target version, filling in defaults if the source did not carry it.
+/// If the target version is too old for the block, it is reported as dropped unless it held
+/// only default values.
+fn keep<T: Default + PartialEq>(
+    part: Option<T>,
+    since: u8,
+    version: u8,
+    dropped: &mut Vec<DroppedData>,
+    report: fn(T) -> DroppedData,
+) -> T {
+    let part = part.unwrap_or_default();
+    if version < since && part != T::default() {
+        dropped.push(report(part));
+        return T::default();
+    }
+
+    part
+}
+
+impl ProtocolVer {
+    /// Convert into the given game info version, filling in defaults for blocks the current
+    /// version does not carry. Returns the converted data along with everything that was lost.
+    pub fn to_version(&self, version: u8) -> Result<(ProtocolVer, Vec<DroppedData>), Error> {
+        check_game_info_version(version)?;
+
+        let parts = self.clone().into_parts();
+        let mut dropped = vec![];
+
+        let v2 = keep(
+            parts.v2,
+            2,
+            version,
+            &mut dropped,
+            DroppedData::CompanyLimits,
+        );
+        let mut v3 = parts.v3.unwrap_or_default();
+        if version < 3 && !v3.fits_original_dates() {
+            dropped.push(DroppedData::Dates(std::mem::take(&mut v3)));
+        }
+        let v4 = keep(parts.v4, 4, version, &mut dropped, |v4| {
+            DroppedData::NewGRFs(v4.active_newgrf)
+        });
+        let v5 = keep(parts.v5, 5, version, &mut dropped, DroppedData::GameScript);
+        let v6 = keep(parts.v6, 6, version, &mut dropped, |v6| {
+            DroppedData::NewGRFSerialisation(v6.newgrf_serialisation)
+        });
+        let v7 = keep(parts.v7, 7, version, &mut dropped, |v7| {
+            DroppedData::TicksPlaying(v7.ticks_playing)
+        });
+
+        let protocol_ver = ProtocolVer::from_parts(version, v2, v3, v4, v5, v6, v7)?;
+
+        Ok((protocol_ver, dropped))
+    }
+}
+
+impl ServerResponse {
+    /// Convert into the given game info version, e.g. to answer a client in the version it
+    /// asked for. Returns the converted response along with everything that was lost.
+    pub fn to_version(&self, version: u8) -> Result<(ServerResponse, Vec<DroppedData>), Error> {
+        let (protocol_ver, mut dropped) = self.protocol_ver.to_version(version)?;
+
+        let mut response = ServerResponse {
+            protocol_ver,
+            ..self.clone()
+        };
+
+        if let Some((server_lang, map_name)) = response.take_unsent_lang_and_map_name() {
+            if server_lang != NetworkLanguage::Any {
+                dropped.push(DroppedData::ServerLang(server_lang));
+            }
+            if !map_name.as_bytes().is_empty() {
+                dropped.push(DroppedData::MapName(map_name));
+            }
+        }
+
+        Ok((response, dropped))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::date::*;
+
+    #[test]
+    fn test_convert_server_response() {
+        let srv = ServerResponse::builder()
+            .version(4)
+            .server_name(CString::new("Test Server").unwrap())
+            .server_lang(NetworkLanguage::German)
+            .map_name(CString::new("Random Map").unwrap())
+            .current_companies(2)
+            .newgrf(0x4D47_0101, NewGRFHash([0xAA; 16]))
+            .build()
+            .unwrap();
+
+        let (v2, dropped) = srv.to_version(2).unwrap();
+        assert_eq!(u8::from(&v2.protocol_ver), 2);
+        assert_eq!(v2.server_lang, NetworkLanguage::German);
+        assert_eq!(
+            dropped,
+            vec![DroppedData::NewGRFs(
+                hashmap! { 0x4D47_0101 => NewGRFHash([0xAA; 16]) }
+            )]
+        );
+
+        let (v7, dropped) = v2.to_version(7).unwrap();
+        match v7.protocol_ver {
+            ProtocolVer::V7(ref v2data, ref v3data, ref v4data, ref v5data, _, ref v7data) => {
+                assert_eq!(v2data.current_companies, 2);
+                assert_eq!(v3data.game_date, OpenTTDDate::from_ymd(1950, 1, 1).unwrap());
+                assert!(v4data.active_newgrf.is_empty());
+                assert_eq!(v5data.gamescript_version, -1);
+                assert_eq!(v7data.ticks_playing, 0);
+            }
+            ref other => panic!("unexpected version {:?}", other),
+        }
+        assert_eq!(v7.server_lang, NetworkLanguage::Any);
+        assert_eq!(
+            dropped,
+            vec![
+                DroppedData::ServerLang(NetworkLanguage::German),
+                DroppedData::MapName(CString::new("Random Map").unwrap()),
+            ]
+        );
+
+        let late = OpenTTDDate::from_ymd(2100, 1, 1).unwrap();
+        let srv = ServerResponse::builder().game_date(late).build().unwrap();
+        let (v2, dropped) = srv.to_version(2).unwrap();
+        assert_eq!(v2.protocol_ver.dates(), &V3Data::default());
+        assert_eq!(
+            dropped,
+            vec![DroppedData::Dates(V3Data {
+                game_date: late,
+                start_date: OpenTTDDate::from_ymd(1950, 1, 1).unwrap(),
+            })]
+        );
+
+        assert_eq!(
+            srv.to_version(8).unwrap_err(),
+            Error::UnsupportedType {
+                kind: "game info version",
+                value: 8
+            }
+        );
+    }
+}
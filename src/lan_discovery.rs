@@ -0,0 +1,347 @@
+use crate::master_response_list::SEND_MTU;
+use crate::query_client::NETWORK_DEFAULT_PORT;
+use crate::server_response::*;
+use crate::util::*;
+use crate::Packet;
+
+use if_addrs::IfAddr;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6, UdpSocket};
+use std::time::{Duration, Instant};
+
+/// IPv6 all-nodes multicast group, the IPv6 counterpart of broadcasting
+const IPV6_ALL_NODES: Ipv6Addr = Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 1);
+
+/// Server found on the local network
+#[derive(Clone, Debug, PartialEq)]
+pub struct LanServer {
+    /// First reply received
+    pub response: ServerResponse,
+    /// Every address the server replied from, in order of arrival. Replies are taken to come
+    /// from the same server if they arrive on the same port with identical contents, which the
+    /// game date and ticks played make unlikely for different servers.
+    pub addrs: Vec<SocketAddr>,
+}
+
+impl LanServer {
+    /// Whether a reply comes from this server reached on another address
+    fn matches(&self, from: SocketAddr, response: &ServerResponse) -> bool {
+        self.addrs.iter().any(|addr| addr.port() == from.port()) && self.response == *response
+    }
+}
+
+/// Add a reply to the server it came from, or list it as a new server
+fn add_reply(servers: &mut Vec<LanServer>, from: SocketAddr, response: ServerResponse) {
+    match servers
+        .iter_mut()
+        .find(|server| server.matches(from, &response))
+    {
+        Some(server) => {
+            if !server.addrs.contains(&from) {
+                server.addrs.push(from);
+            }
+        }
+        None => servers.push(LanServer {
+            response,
+            addrs: vec![from],
+        }),
+    }
+}
+
+/// Broadcast addresses and IPv6 interface indexes of every interface that is not loopback,
+/// like OpenTTD's `NetworkFindBroadcastIPs`. Falls back to `255.255.255.255` and the default
+/// IPv6 interface where nothing is found.
+fn interface_targets() -> (Vec<Ipv4Addr>, Vec<u32>) {
+    let mut broadcast_addrs = vec![];
+    let mut ipv6_interfaces = vec![];
+    for interface in if_addrs::get_if_addrs().unwrap_or_default() {
+        if interface.is_loopback() {
+            continue;
+        }
+        match interface.addr {
+            IfAddr::V4(v4) => {
+                if let Some(broadcast) = v4.broadcast {
+                    if !broadcast_addrs.contains(&broadcast) {
+                        broadcast_addrs.push(broadcast);
+                    }
+                }
+            }
+            IfAddr::V6(_) => {
+                if let Some(index) = interface.index {
+                    if !ipv6_interfaces.contains(&index) {
+                        ipv6_interfaces.push(index);
+                    }
+                }
+            }
+        }
+    }
+
+    if broadcast_addrs.is_empty() {
+        broadcast_addrs.push(Ipv4Addr::BROADCAST);
+    }
+    if ipv6_interfaces.is_empty() {
+        ipv6_interfaces.push(0);
+    }
+
+    (broadcast_addrs, ipv6_interfaces)
+}
+
+/// Finds servers on the local network by broadcasting `ClientFindServer` on every interface,
+/// like OpenTTD's LAN server search
+#[derive(Clone, Debug)]
+pub struct LanDiscovery {
+    port: u16,
+    window: Duration,
+    /// `None` to search every interface
+    broadcast_addrs: Option<Vec<Ipv4Addr>>,
+    /// `None` to search every interface
+    ipv6_interfaces: Option<Vec<u32>>,
+}
+
+impl Default for LanDiscovery {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LanDiscovery {
+    pub fn new() -> Self {
+        Self {
+            port: NETWORK_DEFAULT_PORT,
+            window: Duration::from_secs(1),
+            broadcast_addrs: None,
+            ipv6_interfaces: None,
+        }
+    }
+
+    /// Port servers are expected to listen on, defaults to 3979
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    /// Time to collect replies after broadcasting, defaults to 1 second
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// IPv4 addresses to send to instead of the broadcast address of every interface, e.g.
+    /// `192.168.1.255` to search a single network. Pass an empty list to skip IPv4.
+    pub fn broadcast_addrs(mut self, broadcast_addrs: Vec<Ipv4Addr>) -> Self {
+        self.broadcast_addrs = Some(broadcast_addrs);
+        self
+    }
+
+    /// Interface indexes to send the IPv6 all-nodes multicast on instead of every interface.
+    /// Pass an empty list to skip IPv6.
+    pub fn ipv6_interfaces(mut self, ipv6_interfaces: Vec<u32>) -> Self {
+        self.ipv6_interfaces = Some(ipv6_interfaces);
+        self
+    }
+
+    /// IPv4 addresses and IPv6 interface indexes `discover` sends to
+    fn targets(&self) -> (Vec<Ipv4Addr>, Vec<u32>) {
+        match (self.broadcast_addrs.clone(), self.ipv6_interfaces.clone()) {
+            (Some(broadcast_addrs), Some(ipv6_interfaces)) => (broadcast_addrs, ipv6_interfaces),
+            (broadcast_addrs, ipv6_interfaces) => {
+                let (found_addrs, found_interfaces) = interface_targets();
+                (
+                    broadcast_addrs.unwrap_or(found_addrs),
+                    ipv6_interfaces.unwrap_or(found_interfaces),
+                )
+            }
+        }
+    }
+
+    /// Broadcast on every configured address and collect replies until the window closes.
+    /// Fails only if no request could be sent at all.
+    pub fn discover(&self) -> io::Result<Vec<LanServer>> {
+        let request = Packet::ClientFindServer.to_bytes()?;
+        let (broadcast_addrs, ipv6_interfaces) = self.targets();
+        let mut sockets = vec![];
+        let mut last_error = None;
+
+        if !broadcast_addrs.is_empty() {
+            let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+            socket.set_broadcast(true)?;
+            let mut sent = false;
+            for addr in broadcast_addrs.iter() {
+                match socket.send_to(&request, SocketAddrV4::new(*addr, self.port)) {
+                    Ok(_) => sent = true,
+                    Err(e) => last_error = Some(e),
+                }
+            }
+            if sent {
+                sockets.push(socket);
+            }
+        }
+
+        if !ipv6_interfaces.is_empty() {
+            // Hosts without IPv6 cannot bind here, which only rules out IPv6 discovery
+            match UdpSocket::bind((Ipv6Addr::UNSPECIFIED, 0)) {
+                Ok(socket) => {
+                    let mut sent = false;
+                    for scope_id in ipv6_interfaces.iter() {
+                        let addr = SocketAddrV6::new(IPV6_ALL_NODES, self.port, 0, *scope_id);
+                        match socket.send_to(&request, addr) {
+                            Ok(_) => sent = true,
+                            Err(e) => last_error = Some(e),
+                        }
+                    }
+                    if sent {
+                        sockets.push(socket);
+                    }
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        if sockets.is_empty() {
+            return Err(last_error.unwrap_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "no addresses to search")
+            }));
+        }
+
+        let mut servers: Vec<LanServer> = vec![];
+        let mut buf = [0; SEND_MTU];
+        let deadline = Instant::now() + self.window;
+        // Take turns on the sockets so that neither blocks the other for long
+        let slice = Duration::from_millis(10);
+        while Instant::now() < deadline {
+            for socket in sockets.iter() {
+                let until = (Instant::now() + slice).min(deadline);
+                let (len, from) = match recv_until(socket, &mut buf, until)? {
+                    Some(v) => v,
+                    None => continue,
+                };
+                let response = match Packet::from_incoming_bytes(&buf[..len]) {
+                    Ok(Packet::ServerResponse(response)) => response,
+                    _ => continue,
+                };
+                add_reply(&mut servers, unmap_ipv4(from), response);
+            }
+        }
+
+        Ok(servers)
+    }
+}
+
+/// Turn IPv4-mapped IPv6 addresses back into IPv4 ones
+fn unmap_ipv4(addr: SocketAddr) -> SocketAddr {
+    match addr {
+        SocketAddr::V6(v6) => match v6.ip().to_ipv4_mapped() {
+            Some(v4) => SocketAddr::new(IpAddr::V4(v4), v6.port()),
+            None => addr,
+        },
+        SocketAddr::V4(_) => addr,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::StandIn;
+    use std::ffi::CString;
+
+    #[test]
+    fn test_discover() {
+        // One server reachable on two addresses, and another one next to it
+        let port = StandIn::new("LAN Server").spawn().port();
+        StandIn::new("LAN Server")
+            .bind(SocketAddr::from(([127, 0, 0, 2], port)))
+            .spawn();
+        StandIn::new("Other Server")
+            .bind(SocketAddr::from(([127, 0, 0, 3], port)))
+            .spawn();
+
+        let servers = LanDiscovery::new()
+            .port(port)
+            .window(Duration::from_millis(300))
+            .broadcast_addrs(vec![
+                Ipv4Addr::new(127, 0, 0, 1),
+                Ipv4Addr::new(127, 0, 0, 2),
+                Ipv4Addr::new(127, 0, 0, 3),
+            ])
+            .ipv6_interfaces(vec![])
+            .discover()
+            .unwrap();
+
+        let mut names: Vec<_> = servers
+            .iter()
+            .map(|server| server.response.server_name.to_str().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["LAN Server", "Other Server"]);
+
+        let server = servers
+            .iter()
+            .find(|server| server.response.server_name.to_str() == Ok("LAN Server"))
+            .unwrap();
+        let mut addrs = server.addrs.clone();
+        addrs.sort();
+        assert_eq!(
+            addrs,
+            vec![
+                SocketAddr::from(([127, 0, 0, 1], port)),
+                SocketAddr::from(([127, 0, 0, 2], port)),
+            ]
+        );
+
+        assert!(LanDiscovery::new()
+            .broadcast_addrs(vec![])
+            .ipv6_interfaces(vec![])
+            .discover()
+            .is_err());
+    }
+
+    #[test]
+    fn test_add_reply() {
+        let response = ServerResponse::builder()
+            .server_name(CString::new("LAN Server").unwrap())
+            .build()
+            .unwrap();
+        let mut other = response.clone();
+        other.clients_on = 1;
+
+        let v4 = SocketAddr::from(([192, 168, 1, 2], 3979));
+        let v6 = SocketAddr::from((Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 2), 3979));
+        let next_door = SocketAddr::from(([192, 168, 1, 3], 3979));
+        let other_port = SocketAddr::from(([192, 168, 1, 2], 3980));
+
+        let mut servers = vec![];
+        add_reply(&mut servers, v4, response.clone());
+        add_reply(&mut servers, v6, response.clone());
+        add_reply(&mut servers, v4, response.clone());
+        add_reply(&mut servers, next_door, other);
+        add_reply(&mut servers, other_port, response.clone());
+
+        assert_eq!(servers.len(), 3);
+        assert_eq!(servers[0].addrs, vec![v4, v6]);
+        assert_eq!(servers[1].addrs, vec![next_door]);
+        assert_eq!(servers[2].addrs, vec![other_port]);
+
+        assert_eq!(
+            unmap_ipv4(SocketAddr::new(
+                IpAddr::V6(Ipv4Addr::new(192, 168, 1, 2).to_ipv6_mapped()),
+                3979
+            )),
+            v4
+        );
+    }
+
+    #[test]
+    fn test_interface_targets() {
+        let (broadcast_addrs, ipv6_interfaces) = LanDiscovery::new().targets();
+        assert!(!broadcast_addrs.is_empty());
+        assert!(!ipv6_interfaces.is_empty());
+        assert!(broadcast_addrs.iter().all(|addr| !addr.is_loopback()));
+
+        let (broadcast_addrs, ipv6_interfaces) = LanDiscovery::new()
+            .broadcast_addrs(vec![Ipv4Addr::new(192, 168, 1, 255)])
+            .ipv6_interfaces(vec![])
+            .targets();
+        assert_eq!(broadcast_addrs, vec![Ipv4Addr::new(192, 168, 1, 255)]);
+        assert!(ipv6_interfaces.is_empty());
+    }
+}
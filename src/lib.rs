@@ -1,17 +1,47 @@
-#![allow(unreachable_code)]
-
+#[cfg(test)]
 #[macro_use]
 extern crate maplit;
-#[macro_use]
-extern crate nom;
+
+mod error;
+pub use crate::error::*;
 
 mod util;
 use util::*;
 
+mod date;
+pub use crate::date::*;
+
+mod limits;
+pub use crate::limits::*;
+
+mod text;
+pub use crate::text::*;
+
+mod money;
+pub use crate::money::*;
+
+mod network_language;
+pub use crate::network_language::*;
+
 mod server_response;
-pub use crate::server_response::{ProtocolVer, ServerResponse, V2Data, V3Data, V4Data};
+pub use crate::server_response::{
+    Landscape, NewGRFHash, NewGRFSerialisation, ProtocolVer, ProtocolVerParts, ServerResponse,
+    V2Data, V3Data, V4Data, V5Data, V6Data, V7Data, NETWORK_GAME_INFO_VERSION,
+};
 use server_response::*;
 
+mod server_response_builder;
+pub use crate::server_response_builder::*;
+
+mod game_info_version;
+pub use crate::game_info_version::*;
+
+mod network_game_info;
+pub use crate::network_game_info::*;
+
+mod revision;
+pub use crate::revision::*;
+
 mod server_detail_info;
 pub use crate::server_detail_info::*;
 
@@ -27,11 +57,40 @@ pub use crate::master_response_list::*;
 mod server_unregister;
 pub use crate::server_unregister::*;
 
-use byteorder::{LittleEndian, WriteBytesExt};
+mod client_get_newgrfs;
+pub use crate::client_get_newgrfs::*;
+
+mod server_newgrfs;
+pub use crate::server_newgrfs::*;
+
+mod master_session_key;
+pub use crate::master_session_key::*;
+
+mod registration;
+pub use crate::registration::*;
+
+mod query_client;
+pub use crate::query_client::*;
+
+mod crawler;
+pub use crate::crawler::*;
+
+mod lan_discovery;
+pub use crate::lan_discovery::*;
+
+#[cfg(test)]
+mod test_server;
+
+#[cfg(feature = "tokio")]
+mod codec;
+#[cfg(feature = "tokio")]
+pub use crate::codec::*;
+
+use byteorder::{ByteOrder, LittleEndian, WriteBytesExt};
 use nom::{number::complete::*, *};
 
 /// Enum representing various OpenTTD UDP packet types.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum PacketType {
     /// Queries a game server for game information
     ClientFindServer,
@@ -114,6 +173,9 @@ pub enum Packet {
     ClientGetList(ClientGetListData),
     MasterResponseList(ServerList),
     ServerUnregister(ServerUnregisterData),
+    ClientGetNewGRFs(ClientGetNewGRFsData),
+    ServerNewGRFs(ServerNewGRFsData),
+    MasterSessionKey(MasterSessionKeyData),
 }
 
 impl Packet {
@@ -129,15 +191,16 @@ impl Packet {
             Packet::ClientGetList(_) => PacketType::ClientGetList,
             Packet::MasterResponseList(_) => PacketType::MasterResponseList,
             Packet::ServerUnregister(_) => PacketType::ServerUnregister,
+            Packet::ClientGetNewGRFs(_) => PacketType::ClientGetNewGRFs,
+            Packet::ServerNewGRFs(_) => PacketType::ServerNewGRFs,
+            Packet::MasterSessionKey(_) => PacketType::MasterSessionKey,
         }
     }
 
-    /// Parse a UDP packet
-    named!(pub from_incoming_bytes(&[u8]) -> Packet,
+    named!(parse_packet<&[u8], Packet, Error>,
         do_parse!(
             _packet_len: le_u16 >>
-            packet_type: map_opt!(le_u8, PacketType::from_num) >>
-            packet: switch!(value!(packet_type),
+            packet: switch!(call!(typed_u8, "packet", PacketType::from_num),
                 PacketType::ClientFindServer => value!(Packet::ClientFindServer) |
                 PacketType::ServerResponse => map!(parse_server_response, Packet::ServerResponse) |
                 PacketType::ClientDetailInfo => value!(Packet::ClientDetailInfo) |
@@ -147,14 +210,93 @@ impl Packet {
                 PacketType::ClientGetList => map!(parse_client_get_list, Packet::ClientGetList) |
                 PacketType::MasterResponseList => map!(parse_master_response, Packet::MasterResponseList) |
                 PacketType::ServerUnregister => map!(ServerUnregisterData::from_bytes, Packet::ServerUnregister) |
-                _ => value!(unimplemented!())
+                PacketType::ClientGetNewGRFs => map!(parse_client_get_newgrfs, Packet::ClientGetNewGRFs) |
+                PacketType::ServerNewGRFs => map!(parse_server_newgrfs, Packet::ServerNewGRFs) |
+                PacketType::MasterSessionKey => map!(parse_master_session_key, Packet::MasterSessionKey)
             ) >>
             (packet)
         )
     );
+
+    /// Split the first packet off the buffer according to its length header
+    fn split_packet(input: &[u8]) -> Result<(&[u8], &[u8]), Error> {
+        if input.len() < 2 {
+            return Err(Error::Truncated);
+        }
+
+        let declared = LittleEndian::read_u16(input) as usize;
+        if declared < 3 || declared > input.len() {
+            return Err(Error::BadLength {
+                declared,
+                actual: input.len(),
+            });
+        }
+
+        Ok(input.split_at(declared))
+    }
+
+    /// Parse a single packet whose length header has already been checked
+    fn parse_exact(input: &[u8]) -> Result<Packet, Error> {
+        let (rest, packet) = Self::parse_packet(input).map_err(Error::from_nom)?;
+        if !rest.is_empty() {
+            return Err(Error::TrailingBytes(rest.len()));
+        }
+
+        Ok(packet)
+    }
+
+    /// Parse a UDP packet. The length header must match the size of the input exactly.
+    pub fn from_incoming_bytes(input: &[u8]) -> Result<Packet, Error> {
+        let (data, rest) = Self::split_packet(input)?;
+        if !rest.is_empty() {
+            return Err(Error::BadLength {
+                declared: data.len(),
+                actual: input.len(),
+            });
+        }
+
+        Self::parse_exact(data)
+    }
+
+    /// Parse a UDP packet, ignoring any data beyond the declared length or left unread in the payload
+    pub fn from_incoming_bytes_lenient(input: &[u8]) -> Result<Packet, Error> {
+        let (data, _) = Self::split_packet(input)?;
+
+        Ok(Self::parse_packet(data).map_err(Error::from_nom)?.1)
+    }
+
+    /// Parse a buffer containing several back-to-back packets
+    pub fn split_incoming_bytes(mut input: &[u8]) -> Result<Vec<Packet>, Error> {
+        let mut out = vec![];
+        while !input.is_empty() {
+            let (data, rest) = Self::split_packet(input)?;
+            out.push(Self::parse_exact(data)?);
+            input = rest;
+        }
+
+        Ok(out)
+    }
 }
 
 impl Packet {
+    /// Shorten all strings in the packet to the lengths OpenTTD accepts
+    pub fn truncate_to_limits(&mut self) {
+        match *self {
+            Packet::ServerResponse(ref mut data) => data.truncate_to_limits(),
+            Packet::ServerDetailInfo(ref mut data) => data.truncate_to_limits(),
+            Packet::ServerRegister(ref mut data) => data.truncate_to_limits(),
+            Packet::ServerNewGRFs(ref mut data) => data.truncate_to_limits(),
+            _ => {}
+        }
+    }
+
+    /// Like `to_bytes`, but truncates over-long strings instead of failing
+    pub fn to_bytes_truncated(&self) -> std::io::Result<Vec<u8>> {
+        let mut pkt = self.clone();
+        pkt.truncate_to_limits();
+        pkt.to_bytes()
+    }
+
     pub fn to_bytes(&self) -> std::io::Result<Vec<u8>> {
         let buf = &mut vec![];
         buf.push(self.pkt_type().into());
@@ -166,9 +308,23 @@ impl Packet {
             Packet::ClientGetList(ref data) => data.write_pkt(buf)?,
             Packet::MasterResponseList(ref data) => data.write_pkt(buf)?,
             Packet::ServerUnregister(ref data) => data.write_pkt(buf)?,
+            Packet::ClientGetNewGRFs(ref data) => data.write_pkt(buf)?,
+            Packet::ServerNewGRFs(ref data) => data.write_pkt(buf)?,
+            Packet::MasterSessionKey(ref data) => data.write_pkt(buf)?,
             _ => {}
         };
 
+        // The length header counts itself
+        if buf.len() > usize::from(u16::MAX) - 2 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "packet of {} bytes does not fit the length header",
+                    buf.len() + 2
+                ),
+            ));
+        }
+
         let mut out = vec![];
         out.write_u16::<LittleEndian>(buf.len() as u16 + 2)?;
         out.append(buf);
@@ -194,7 +350,61 @@ mod tests {
 
         let result = Packet::from_incoming_bytes(&input).unwrap();
 
-        assert_eq!(expectation, result.1);
+        assert_eq!(expectation, result);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Packet::from_incoming_bytes(&[3, 0, 42]),
+            Err(Error::UnknownType {
+                kind: "packet",
+                value: 42
+            })
+        );
+        assert_eq!(
+            Packet::from_incoming_bytes(&[4, 0, 8, 2]),
+            Err(Error::Truncated)
+        );
+        assert_eq!(
+            Packet::from_incoming_bytes(&[4, 0, 2, 0]),
+            Err(Error::TrailingBytes(1))
+        );
+        // Payload ends inside the welcome message
+        assert_eq!(
+            Packet::from_incoming_bytes(&[5, 0, 4, b'h', b'i']),
+            Err(Error::Truncated)
+        );
+    }
+
+    #[test]
+    fn test_packet_length() {
+        assert_eq!(
+            Packet::from_incoming_bytes(&[5, 0, 2]),
+            Err(Error::BadLength {
+                declared: 5,
+                actual: 3
+            })
+        );
+        assert_eq!(
+            Packet::from_incoming_bytes(&[3, 0, 2, 3, 0]),
+            Err(Error::BadLength {
+                declared: 3,
+                actual: 5
+            })
+        );
+        assert_eq!(
+            Packet::from_incoming_bytes_lenient(&[3, 0, 2, 3, 0]),
+            Ok(Packet::ClientDetailInfo)
+        );
+        assert_eq!(
+            Packet::split_incoming_bytes(&[3, 0, 2, 3, 0, 0, 3, 0, 5]),
+            Ok(vec![
+                Packet::ClientDetailInfo,
+                Packet::ClientFindServer,
+                Packet::MasterAckRegister
+            ])
+        );
     }
 
     #[test]
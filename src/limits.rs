@@ -0,0 +1,125 @@
+use std::ffi::CString;
+
+/// Maximum length of server, map and GameScript names, including the terminating NUL
+pub const NETWORK_NAME_LENGTH: usize = 80;
+/// Maximum length of company names
+pub const NETWORK_COMPANY_NAME_LENGTH: usize = 128;
+/// Maximum length of the server revision string
+pub const NETWORK_REVISION_LENGTH: usize = 33;
+/// Maximum length of NewGRF names
+pub const NETWORK_GRF_NAME_LENGTH: usize = 80;
+/// Maximum length of client names
+pub const NETWORK_CLIENT_NAME_LENGTH: usize = 25;
+/// Maximum length of the unique client ID
+pub const NETWORK_UNIQUE_ID_LENGTH: usize = 33;
+/// Maximum number of NewGRFs that can be sent in a packet
+pub const NETWORK_MAX_GRF_COUNT: usize = 255;
+/// Maximum number of companies in a game
+pub const MAX_COMPANIES: usize = 15;
+
+/// Check that a string fits into `limit` bytes including its NUL, returning `(field, len, limit)`
+/// if it does not
+pub(crate) fn check_cstring_len<'a>(
+    s: &CString,
+    limit: usize,
+    field: &'a str,
+) -> Result<(), (&'a str, usize, usize)> {
+    let len = s.as_bytes().len();
+    if len >= limit {
+        return Err((field, len, limit));
+    }
+
+    Ok(())
+}
+
+/// Describe a string rejected by `check_cstring_len`
+pub(crate) fn string_too_long_message(field: &str, len: usize, limit: usize) -> String {
+    format!(
+        "{} is {} bytes long, OpenTTD allows at most {}",
+        field,
+        len,
+        limit - 1
+    )
+}
+
+/// Write a NUL-terminated string, failing if it does not fit into `limit` bytes
+pub(crate) fn write_cstring(
+    buf: &mut Vec<u8>,
+    s: &CString,
+    limit: usize,
+    field: &str,
+) -> std::io::Result<()> {
+    check_cstring_len(s, limit, field).map_err(|(field, len, limit)| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            string_too_long_message(field, len, limit),
+        )
+    })?;
+
+    buf.extend_from_slice(s.as_bytes_with_nul());
+
+    Ok(())
+}
+
+pub(crate) fn write_newgrf_count(len: usize, buf: &mut Vec<u8>) -> std::io::Result<()> {
+    if len > NETWORK_MAX_GRF_COUNT {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("NewGRF maximum number is {}", NETWORK_MAX_GRF_COUNT),
+        ));
+    }
+
+    buf.push(len as u8);
+
+    Ok(())
+}
+
+/// Shorten the string so that it fits into `limit` bytes, without splitting UTF-8 sequences
+pub fn truncate_cstring(s: &mut CString, limit: usize) {
+    let bytes = s.as_bytes();
+    if bytes.len() < limit {
+        return;
+    }
+
+    let mut end = limit.saturating_sub(1);
+    // Step back over continuation bytes so that no multi-byte sequence is cut in half
+    while end > 0 && end < bytes.len() && bytes[end] & 0xC0 == 0x80 {
+        end -= 1;
+    }
+
+    *s = CString::new(&bytes[..end]).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_cstring() {
+        let mut s = CString::new("Übertragung").unwrap();
+        truncate_cstring(&mut s, 3);
+        assert_eq!(s.to_str().unwrap(), "Ü");
+
+        let mut s = CString::new("Übertragung").unwrap();
+        truncate_cstring(&mut s, 2);
+        assert_eq!(s.to_str().unwrap(), "");
+
+        let mut s = CString::new("short").unwrap();
+        truncate_cstring(&mut s, 80);
+        assert_eq!(s.to_str().unwrap(), "short");
+    }
+
+    #[test]
+    fn test_write_cstring_limit() {
+        let mut buf = vec![];
+        let s = CString::new("x".repeat(80)).unwrap();
+
+        let err = write_cstring(&mut buf, &s, NETWORK_NAME_LENGTH, "server name").unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "server name is 80 bytes long, OpenTTD allows at most 79"
+        );
+        assert!(buf.is_empty());
+    }
+}
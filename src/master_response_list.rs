@@ -1,10 +1,11 @@
+use crate::error::*;
 use crate::util::*;
+use crate::Packet;
 
 use byteorder::{LittleEndian, WriteBytesExt};
 use nom::{self, number::complete::*, *};
-use std;
 use std::collections::HashSet;
-use std::net::{Ipv4Addr, Ipv6Addr, SocketAddrV4, SocketAddrV6};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
 
 #[derive(Clone, Copy, Debug, PartialEq, Hash)]
 enum ServerType {
@@ -12,6 +13,17 @@ enum ServerType {
     IPv6,
 }
 
+impl From<ServerType> for u8 {
+    fn from(v: ServerType) -> Self {
+        use self::ServerType::*;
+
+        match v {
+            IPv4 => 1,
+            IPv6 => 2,
+        }
+    }
+}
+
 impl ServerType {
     fn from_num(v: u8) -> Option<Self> {
         use self::ServerType::*;
@@ -24,11 +36,36 @@ impl ServerType {
     }
 }
 
+/// Largest UDP packet OpenTTD sends, including the packet header
+pub const SEND_MTU: usize = 1460;
+
+/// Packet length, packet type, server type and server count
+const MASTER_RESPONSE_HEADER_SIZE: usize = 6;
+const V4_ENTRY_SIZE: usize = 6;
+const V6_ENTRY_SIZE: usize = 18;
+
+/// Write the server count, failing if it does not fit into its `u16` field
+fn write_server_count(len: usize, buf: &mut Vec<u8>) -> std::io::Result<()> {
+    if len > u16::MAX as usize {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "{} servers do not fit into one list, use `ServerList::paginate`",
+                len
+            ),
+        ));
+    }
+
+    buf.write_u16::<LittleEndian>(len as u16)
+}
+
+/// IPv4 servers in wire order, duplicates included
+pub type V4List = Vec<SocketAddrV4>;
 pub type V4Set = HashSet<SocketAddrV4>;
 
-impl ByteWriter for V4Set {
+impl ByteWriter for V4List {
     fn write_pkt(&self, buf: &mut Vec<u8>) -> std::io::Result<()> {
-        buf.write_u16::<LittleEndian>(self.len() as u16)?;
+        write_server_count(self.len(), buf)?;
         for addr in self.iter() {
             for octet in &addr.ip().octets() {
                 buf.write_u8(*octet)?;
@@ -40,15 +77,15 @@ impl ByteWriter for V4Set {
     }
 }
 
+/// IPv6 servers in wire order, duplicates included
+pub type V6List = Vec<SocketAddrV6>;
 pub type V6Set = HashSet<SocketAddrV6>;
 
-impl ByteWriter for V6Set {
+impl ByteWriter for V6List {
     fn write_pkt(&self, buf: &mut Vec<u8>) -> std::io::Result<()> {
-        buf.write_u16::<LittleEndian>(self.len() as u16)?;
+        write_server_count(self.len(), buf)?;
         for addr in self.iter() {
-            for segment in &addr.ip().segments() {
-                buf.write_u16::<LittleEndian>(*segment)?;
-            }
+            buf.extend_from_slice(&addr.ip().octets());
             buf.write_u16::<LittleEndian>(addr.port())?;
         }
 
@@ -58,20 +95,145 @@ impl ByteWriter for V6Set {
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum ServerList {
-    IPv4(V4Set),
-    IPv6(V6Set),
+    IPv4(V4List),
+    IPv6(V6List),
+}
+
+impl ServerList {
+    /// All servers in the list, regardless of address family
+    pub fn socket_addrs(&self) -> Vec<SocketAddr> {
+        match *self {
+            ServerList::IPv4(ref data) => data.iter().cloned().map(SocketAddr::V4).collect(),
+            ServerList::IPv6(ref data) => data.iter().cloned().map(SocketAddr::V6).collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match *self {
+            ServerList::IPv4(ref data) => data.len(),
+            ServerList::IPv6(ref data) => data.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Copy of the list with duplicate addresses removed, keeping the first occurrence
+    pub fn deduped(&self) -> ServerList {
+        match *self {
+            ServerList::IPv4(ref data) => {
+                let mut seen = V4Set::new();
+                ServerList::IPv4(data.iter().filter(|v| seen.insert(**v)).cloned().collect())
+            }
+            ServerList::IPv6(ref data) => {
+                let mut seen = V6Set::new();
+                ServerList::IPv6(data.iter().filter(|v| seen.insert(**v)).cloned().collect())
+            }
+        }
+    }
+
+    /// Split the list into pages that each fit into a `MasterResponseList` packet of at most `mtu` bytes.
+    /// Returns `None` if `mtu` cannot hold the packet header and a single entry.
+    pub fn paginate(&self, mtu: usize) -> Option<Vec<ServerList>> {
+        let room = mtu.saturating_sub(MASTER_RESPONSE_HEADER_SIZE);
+        let pages = match *self {
+            ServerList::IPv4(ref data) => data
+                .chunks(Some(room / V4_ENTRY_SIZE).filter(|&n| n > 0)?)
+                .map(|chunk| ServerList::IPv4(chunk.to_vec()))
+                .collect::<Vec<_>>(),
+            ServerList::IPv6(ref data) => data
+                .chunks(Some(room / V6_ENTRY_SIZE).filter(|&n| n > 0)?)
+                .map(|chunk| ServerList::IPv6(chunk.to_vec()))
+                .collect::<Vec<_>>(),
+        };
+
+        if pages.is_empty() {
+            Some(vec![self.clone()])
+        } else {
+            Some(pages)
+        }
+    }
+
+    /// Split the list into `MasterResponseList` packets no larger than OpenTTD's MTU
+    pub fn to_packets(&self) -> Vec<Packet> {
+        self.paginate(SEND_MTU)
+            .expect("SEND_MTU holds many entries")
+            .into_iter()
+            .map(Packet::MasterResponseList)
+            .collect()
+    }
+}
+
+/// Merges the pages of a server list received in several `MasterResponseList` packets
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ServerListAggregator {
+    pub ipv4: V4List,
+    pub ipv6: V6List,
+}
+
+impl ServerListAggregator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, list: ServerList) {
+        match list {
+            ServerList::IPv4(mut data) => self.ipv4.append(&mut data),
+            ServerList::IPv6(mut data) => self.ipv6.append(&mut data),
+        }
+    }
+
+    /// Add the list carried by a `MasterResponseList` packet, returns false for any other packet
+    pub fn add_packet(&mut self, pkt: &Packet) -> bool {
+        match *pkt {
+            Packet::MasterResponseList(ref list) => {
+                self.add(list.clone());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// All servers received so far, IPv4 first
+    pub fn socket_addrs(&self) -> Vec<SocketAddr> {
+        self.ipv4
+            .iter()
+            .cloned()
+            .map(SocketAddr::V4)
+            .chain(self.ipv6.iter().cloned().map(SocketAddr::V6))
+            .collect()
+    }
+
+    /// Merged lists for every address family that was received
+    pub fn into_lists(self) -> Vec<ServerList> {
+        let mut out = vec![];
+        if !self.ipv4.is_empty() {
+            out.push(ServerList::IPv4(self.ipv4));
+        }
+        if !self.ipv6.is_empty() {
+            out.push(ServerList::IPv6(self.ipv6));
+        }
+        out
+    }
 }
 
 impl ByteWriter for ServerList {
     fn write_pkt(&self, buf: &mut Vec<u8>) -> std::io::Result<()> {
         match *self {
-            ServerList::IPv4(ref data) => data.write_pkt(buf),
-            ServerList::IPv6(ref data) => data.write_pkt(buf),
+            ServerList::IPv4(ref data) => {
+                buf.write_u8(ServerType::IPv4.into())?;
+                data.write_pkt(buf)
+            }
+            ServerList::IPv6(ref data) => {
+                buf.write_u8(ServerType::IPv6.into())?;
+                data.write_pkt(buf)
+            }
         }
     }
 }
 
-named!(parse_v4_ip<&[u8], Ipv4Addr>,
+named!(parse_v4_ip<&[u8], Ipv4Addr, Error>,
     do_parse!(
         a: le_u8 >>
         b: le_u8 >>
@@ -81,21 +243,15 @@ named!(parse_v4_ip<&[u8], Ipv4Addr>,
     )
 );
 
-named!(parse_v6_ip<&[u8], Ipv6Addr>,
-    do_parse!(
-        a: le_u16 >>
-        b: le_u16 >>
-        c: le_u16 >>
-        d: le_u16 >>
-        e: le_u16 >>
-        f: le_u16 >>
-        g: le_u16 >>
-        h: le_u16 >>
-        (Ipv6Addr::new(a, b, c, d, e, f, g, h))
-    )
+named!(parse_v6_ip<&[u8], Ipv6Addr, Error>,
+    map!(take!(16), |v| {
+        let mut octets = [0; 16];
+        octets.copy_from_slice(v);
+        Ipv6Addr::from(octets)
+    })
 );
 
-named!(parse_master_response_v4_server_entry(&[u8]) -> SocketAddrV4,
+named!(parse_master_response_v4_server_entry<&[u8], SocketAddrV4, Error>,
     do_parse!(
         ip: parse_v4_ip >>
         port: le_u16 >>
@@ -103,15 +259,15 @@ named!(parse_master_response_v4_server_entry(&[u8]) -> SocketAddrV4,
     )
 );
 
-named!(parse_master_response_v4(&[u8]) -> HashSet<SocketAddrV4>,
+named!(parse_master_response_v4<&[u8], V4List, Error>,
     do_parse!(
         server_count: le_u16 >>
         servers: count!(parse_master_response_v4_server_entry, server_count.into()) >>
-        (servers.into_iter().collect::<HashSet<_>>())
+        (servers)
     )
 );
 
-named!(parse_master_response_v6_server_entry(&[u8]) -> SocketAddrV6,
+named!(parse_master_response_v6_server_entry<&[u8], SocketAddrV6, Error>,
     do_parse!(
         ip: parse_v6_ip >>
         port: le_u16 >>
@@ -119,22 +275,18 @@ named!(parse_master_response_v6_server_entry(&[u8]) -> SocketAddrV6,
     )
 );
 
-named!(parse_master_response_v6(&[u8]) -> HashSet<SocketAddrV6>,
+named!(parse_master_response_v6<&[u8], V6List, Error>,
     do_parse!(
         server_count: le_u16 >>
         servers: count!(parse_master_response_v6_server_entry, server_count.into()) >>
-        (servers.into_iter().collect::<HashSet<_>>())
+        (servers)
     )
 );
 
-named!(pub parse_master_response(&[u8]) -> ServerList,
-    do_parse!(
-        server_type: map_opt!(le_u8, ServerType::from_num) >>
-        server_lists: switch!(value!(server_type),
-            ServerType::IPv4 => map!(parse_master_response_v4, ServerList::IPv4) |
-            ServerType::IPv6 => map!(parse_master_response_v6, ServerList::IPv6)
-        ) >>
-        (server_lists)
+named!(pub parse_master_response<&[u8], ServerList, Error>,
+    switch!(call!(typed_u8, "server list", ServerType::from_num),
+        ServerType::IPv4 => map!(parse_master_response_v4, ServerList::IPv4) |
+        ServerType::IPv6 => map!(parse_master_response_v6, ServerList::IPv6)
     )
 );
 
@@ -174,7 +326,27 @@ mod tests {
             ]
             .iter()
             .map(|s| SocketAddrV4::from_str(s).unwrap())
-            .collect::<HashSet<SocketAddrV4>>(),
+            .collect::<V4List>(),
+        );
+
+        (data, srv_list)
+    }
+
+    fn fixtures_v6() -> (Vec<u8>, ServerList) {
+        let data = hex!(
+            "
+            020200
+            2A0104F8010A1F8C0000000000000002 8B0F
+            20010DB8000000000000000000000001 8C0F
+        "
+        )
+        .to_vec();
+
+        let srv_list = ServerList::IPv6(
+            ["[2a01:4f8:10a:1f8c::2]:3979", "[2001:db8::1]:3980"]
+                .iter()
+                .map(|s| SocketAddrV6::from_str(s).unwrap())
+                .collect::<V6List>(),
         );
 
         (data, srv_list)
@@ -188,4 +360,135 @@ mod tests {
 
         assert_eq!(expectation, result.1);
     }
+
+    #[test]
+    fn test_parse_master_response_v6() {
+        let (input, expectation) = fixtures_v6();
+
+        let result = parse_master_response(&input).unwrap();
+
+        assert_eq!(expectation, result.1);
+        assert_eq!(result.0.len(), 0);
+    }
+
+    #[test]
+    fn test_write_master_response() {
+        for (input, srv_list) in [fixtures(), fixtures_v6()] {
+            let rest = parse_master_response(&input).unwrap().0;
+            let expectation = &input[..input.len() - rest.len()];
+
+            let mut result = Vec::new();
+            srv_list.write_pkt(&mut result).unwrap();
+
+            assert_eq!(expectation, &result[..]);
+        }
+    }
+
+    #[test]
+    fn test_server_list_deduped() {
+        let addr = SocketAddrV4::from_str("74.208.75.183:3979").unwrap();
+        let other = SocketAddrV4::from_str("83.199.24.22:3979").unwrap();
+        let srv_list = ServerList::IPv4(vec![addr, other, addr]);
+
+        assert_eq!(srv_list.len(), 3);
+        assert_eq!(srv_list.deduped(), ServerList::IPv4(vec![addr, other]));
+    }
+
+    #[test]
+    fn test_server_list_pagination() {
+        let srv_list = ServerList::IPv4(
+            (0..500u32)
+                .map(|i| SocketAddrV4::new(Ipv4Addr::from(0x0a00_0000 + i), 3979))
+                .collect(),
+        );
+
+        let packets = srv_list.to_packets();
+        assert_eq!(packets.len(), 3);
+
+        let mut aggregator = ServerListAggregator::new();
+        for packet in packets {
+            let bytes = packet.to_bytes().unwrap();
+            assert!(bytes.len() <= SEND_MTU);
+
+            assert!(aggregator.add_packet(&Packet::from_incoming_bytes(&bytes).unwrap()));
+        }
+
+        assert_eq!(aggregator.into_lists(), vec![srv_list]);
+    }
+
+    #[test]
+    fn test_server_list_pagination_small_mtu() {
+        let srv_list = ServerList::IPv4(
+            (0..10u32)
+                .map(|i| SocketAddrV4::new(Ipv4Addr::from(0x0a00_0000 + i), 3979))
+                .collect(),
+        );
+
+        assert_eq!(srv_list.paginate(3), None);
+        assert_eq!(
+            srv_list.paginate(MASTER_RESPONSE_HEADER_SIZE + V4_ENTRY_SIZE - 1),
+            None
+        );
+
+        let pages = srv_list
+            .paginate(MASTER_RESPONSE_HEADER_SIZE + V4_ENTRY_SIZE)
+            .unwrap();
+        assert_eq!(pages.len(), 10);
+        for page in pages {
+            let bytes = Packet::MasterResponseList(page).to_bytes().unwrap();
+            assert_eq!(bytes.len(), MASTER_RESPONSE_HEADER_SIZE + V4_ENTRY_SIZE);
+        }
+
+        let v6_list = ServerList::IPv6(vec![]);
+        assert_eq!(
+            v6_list.paginate(MASTER_RESPONSE_HEADER_SIZE + V4_ENTRY_SIZE),
+            None
+        );
+    }
+
+    #[test]
+    fn test_server_list_too_large() {
+        let list = |n: u32| {
+            ServerList::IPv4(
+                (0..n)
+                    .map(|i| SocketAddrV4::new(Ipv4Addr::from(0x0a00_0000 + i), 3979))
+                    .collect(),
+            )
+        };
+
+        // Count does not fit into its u16 field
+        let mut buf = Vec::new();
+        assert!(list(70_000).write_pkt(&mut buf).is_err());
+        assert!(Packet::MasterResponseList(list(70_000)).to_bytes().is_err());
+
+        // Count fits, but the packet is longer than the length header allows
+        assert!(Packet::MasterResponseList(list(11_000)).to_bytes().is_err());
+        assert!(list(11_000)
+            .to_packets()
+            .iter()
+            .all(|packet| packet.to_bytes().is_ok()));
+    }
+
+    #[test]
+    fn test_master_response_roundtrip() {
+        for (_, srv_list) in [fixtures(), fixtures_v6()] {
+            let mut buf = Vec::new();
+            srv_list.write_pkt(&mut buf).unwrap();
+
+            let result = parse_master_response(&buf).unwrap();
+
+            assert_eq!(srv_list, result.1);
+            assert_eq!(result.0.len(), 0);
+        }
+    }
+
+    #[test]
+    fn test_server_list_socket_addrs() {
+        let (_, srv_list) = fixtures_v6();
+
+        let addrs = srv_list.socket_addrs();
+
+        assert_eq!(addrs.len(), 2);
+        assert!(addrs.contains(&SocketAddr::from_str("[2001:db8::1]:3980").unwrap()));
+    }
 }
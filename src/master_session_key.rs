@@ -0,0 +1,73 @@
+use crate::error::*;
+use crate::util::*;
+
+use byteorder::{LittleEndian, WriteBytesExt};
+use nom::{self, number::complete::*, *};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MasterSessionKeyData {
+    pub session_key: u64,
+}
+
+impl ByteWriter for MasterSessionKeyData {
+    fn write_pkt(&self, buf: &mut Vec<u8>) -> std::io::Result<()> {
+        buf.write_u64::<LittleEndian>(self.session_key)?;
+
+        Ok(())
+    }
+}
+
+named!(pub parse_master_session_key<&[u8], MasterSessionKeyData, Error>,
+    do_parse!(
+        session_key: le_u64 >>
+        (MasterSessionKeyData { session_key })
+    )
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Packet;
+
+    use hex_literal::hex;
+
+    fn fixtures() -> (Vec<u8>, MasterSessionKeyData) {
+        let b = hex!("EFCDAB8967452301").to_vec();
+
+        let data = MasterSessionKeyData {
+            session_key: 0x0123_4567_89AB_CDEF,
+        };
+
+        (b, data)
+    }
+
+    #[test]
+    fn test_parse_master_session_key() {
+        let (input, expectation) = fixtures();
+
+        let result = parse_master_session_key(&input).unwrap();
+
+        assert_eq!(expectation, result.1);
+    }
+
+    #[test]
+    fn test_write_master_session_key() {
+        let (expectation, input) = fixtures();
+
+        let mut result = Vec::new();
+        input.write_pkt(&mut result).unwrap();
+
+        assert_eq!(expectation, result);
+    }
+
+    #[test]
+    fn test_master_session_key_packet() {
+        let (_, data) = fixtures();
+        let packet = Packet::MasterSessionKey(data);
+
+        let bytes = packet.to_bytes().unwrap();
+
+        assert_eq!(bytes, hex!("0B00 0B EFCDAB8967452301").to_vec());
+        assert_eq!(Packet::from_incoming_bytes(&bytes), Ok(packet));
+    }
+}
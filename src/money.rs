@@ -0,0 +1,251 @@
+use std::fmt;
+use std::ops::{Add, Neg, Sub};
+
+/// Amount of money in pounds sterling, the base currency of OpenTTD
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money(pub i64);
+
+/// Currency from OpenTTD's built-in currency table
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Currency {
+    /// ISO 4217 code
+    pub code: &'static str,
+    /// Exchange rate from pounds sterling
+    pub rate: u16,
+    /// Year the currency was replaced by the euro
+    pub to_euro: Option<u32>,
+    pub prefix: &'static str,
+    pub suffix: &'static str,
+}
+
+const fn currency(
+    code: &'static str,
+    rate: u16,
+    to_euro: Option<u32>,
+    prefix: &'static str,
+    suffix: &'static str,
+) -> Currency {
+    Currency {
+        code,
+        rate,
+        to_euro,
+        prefix,
+        suffix,
+    }
+}
+
+/// Index of the custom currency in OpenTTD's currency setting
+pub const CUSTOM_CURRENCY_INDEX: u8 = 31;
+
+/// OpenTTD's currencies in game settings order, without the custom currency. Use
+/// `Currency::from_index` to look up a currency by the index OpenTTD stores.
+pub const CURRENCIES: [Currency; 41] = [
+    currency("GBP", 1, None, "£", ""),
+    currency("USD", 2, None, "$", ""),
+    currency("EUR", 2, None, "€", ""),
+    currency("JPY", 220, None, "¥", ""),
+    currency("ATS", 27, Some(2002), "", "\u{a0}S."),
+    currency("BEF", 81, Some(2002), "BEF\u{a0}", ""),
+    currency("CHF", 2, None, "CHF\u{a0}", ""),
+    currency("CZK", 41, None, "", "\u{a0}Kč"),
+    currency("DEM", 4, Some(2002), "DM\u{a0}", ""),
+    currency("DKK", 11, None, "", "\u{a0}kr"),
+    currency("ESP", 333, Some(2002), "Pts\u{a0}", ""),
+    currency("FIM", 12, Some(2002), "", "\u{a0}mk"),
+    currency("FRF", 13, Some(2002), "FF\u{a0}", ""),
+    currency("GRD", 681, Some(2002), "", "Dr."),
+    currency("HUF", 378, None, "", "\u{a0}Ft"),
+    currency("ISK", 130, None, "", "\u{a0}Kr"),
+    currency("ITL", 3873, Some(2002), "", "\u{a0}L."),
+    currency("NLG", 4, Some(2002), "NLG\u{a0}", ""),
+    currency("NOK", 12, None, "", "\u{a0}Kr"),
+    currency("PLN", 6, None, "", "\u{a0}zł"),
+    currency("RON", 5, None, "", "\u{a0}Lei"),
+    currency("RUR", 50, None, "", "\u{a0}p"),
+    currency("SIT", 479, Some(2007), "", "\u{a0}SIT"),
+    currency("SEK", 13, None, "", "\u{a0}Kr"),
+    currency("TRY", 3, None, "", "\u{a0}TL"),
+    currency("SKK", 60, Some(2009), "", "\u{a0}Sk"),
+    currency("BRL", 4, None, "R$\u{a0}", ""),
+    currency("EEK", 31, Some(2011), "", "\u{a0}EEK"),
+    currency("LTL", 4, Some(2015), "", "\u{a0}Lt"),
+    currency("KRW", 1850, None, "₩", ""),
+    currency("ZAR", 13, None, "R\u{a0}", ""),
+    currency("GEL", 3, None, "", "\u{a0}GEL"),
+    currency("IRR", 4901, None, "", "\u{a0}Rls"),
+    currency("RUB", 80, None, "", "\u{a0}rub"),
+    currency("MXN", 24, None, "$", ""),
+    currency("NTD", 40, None, "NTD\u{a0}", ""),
+    currency("CNY", 8, None, "¥", ""),
+    currency("HKD", 10, None, "HK$", ""),
+    currency("INR", 90, None, "₹", ""),
+    currency("IDR", 19733, None, "Rp", ""),
+    currency("MYR", 5, None, "RM", ""),
+];
+
+impl Currency {
+    /// Look up a currency by its ISO 4217 code
+    pub fn from_code(code: &str) -> Option<&'static Currency> {
+        CURRENCIES
+            .iter()
+            .find(|currency| currency.code.eq_ignore_ascii_case(code))
+    }
+
+    /// Look up a currency by its index in OpenTTD's currency setting, `None` for the custom
+    /// currency and unknown indexes
+    pub fn from_index(index: u8) -> Option<&'static Currency> {
+        match index {
+            CUSTOM_CURRENCY_INDEX => None,
+            i if i < CUSTOM_CURRENCY_INDEX => CURRENCIES.get(usize::from(i)),
+            i => CURRENCIES.get(usize::from(i) - 1),
+        }
+    }
+
+    /// Index of the currency in OpenTTD's currency setting
+    pub fn index(&self) -> u8 {
+        let position = CURRENCIES
+            .iter()
+            .position(|currency| currency.code == self.code)
+            .expect("currency from the built-in table") as u8;
+        if position < CUSTOM_CURRENCY_INDEX {
+            position
+        } else {
+            position + 1
+        }
+    }
+
+    /// Currency used in the given in-game year, switching to the euro like OpenTTD does
+    pub fn in_year(&'static self, year: u32) -> &'static Currency {
+        match self.to_euro {
+            Some(to_euro) if year >= to_euro => Currency::from_code("EUR").unwrap(),
+            _ => self,
+        }
+    }
+}
+
+/// Format a number with `,` as thousands separator
+fn group_thousands(number: u128) -> String {
+    let digits = number.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+
+    out
+}
+
+impl Money {
+    /// Format the amount in the given currency, e.g. `-£1,234` or `1,234 Kč`
+    pub fn format(&self, currency: &Currency) -> String {
+        let amount = i128::from(self.0) * i128::from(currency.rate);
+        let sign = if amount < 0 { "-" } else { "" };
+
+        format!(
+            "{}{}{}{}",
+            sign,
+            currency.prefix,
+            group_thousands(amount.unsigned_abs()),
+            currency.suffix
+        )
+    }
+
+    /// Sum of both amounts, `None` on overflow
+    pub fn checked_add(&self, other: Money) -> Option<Money> {
+        self.0.checked_add(other.0).map(Money)
+    }
+
+    /// Difference of both amounts, `None` on overflow
+    pub fn checked_sub(&self, other: Money) -> Option<Money> {
+        self.0.checked_sub(other.0).map(Money)
+    }
+
+    /// Negated amount, `None` for `i64::MIN`
+    pub fn checked_neg(&self) -> Option<Money> {
+        self.0.checked_neg().map(Money)
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    /// Saturates at the bounds of `i64`, see `checked_add`
+    fn add(self, other: Money) -> Money {
+        Money(self.0.saturating_add(other.0))
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    /// Saturates at the bounds of `i64`, see `checked_sub`
+    fn sub(self, other: Money) -> Money {
+        Money(self.0.saturating_sub(other.0))
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    /// Saturates at `i64::MAX` for `i64::MIN`, see `checked_neg`
+    fn neg(self) -> Money {
+        Money(self.0.saturating_neg())
+    }
+}
+
+impl fmt::Display for Money {
+    /// Format in pounds sterling
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", self.format(&CURRENCIES[0]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_money() {
+        assert_eq!(Money(-1_234_567).to_string(), "-£1,234,567");
+        assert_eq!(Money(0).to_string(), "£0");
+
+        let czk = Currency::from_code("czk").unwrap();
+        assert_eq!(Money(1_000).format(czk), "41,000\u{a0}Kč");
+
+        let dem = Currency::from_code("DEM").unwrap();
+        assert_eq!(dem.in_year(2001).code, "DEM");
+        assert_eq!(Money(500).format(dem.in_year(2002)), "€1,000");
+    }
+
+    #[test]
+    fn test_currency_index() {
+        assert_eq!(Currency::from_index(0).unwrap().code, "GBP");
+        assert_eq!(Currency::from_index(30).unwrap().code, "ZAR");
+        assert_eq!(Currency::from_index(CUSTOM_CURRENCY_INDEX), None);
+        assert_eq!(Currency::from_index(32).unwrap().code, "GEL");
+        assert_eq!(Currency::from_index(41).unwrap().code, "MYR");
+        assert_eq!(Currency::from_index(42), None);
+
+        for currency in CURRENCIES.iter() {
+            assert_eq!(Currency::from_index(currency.index()), Some(currency));
+        }
+    }
+
+    #[test]
+    fn test_money_arithmetic() {
+        assert_eq!(Money(3) + Money(4), Money(7));
+        assert_eq!(Money(3) - Money(4), Money(-1));
+        assert_eq!(-Money(3), Money(-3));
+
+        assert_eq!(Money(i64::MAX) + Money(1), Money(i64::MAX));
+        assert_eq!(Money(i64::MIN) - Money(1), Money(i64::MIN));
+        assert_eq!(-Money(i64::MIN), Money(i64::MAX));
+
+        assert_eq!(Money(i64::MAX).checked_add(Money(1)), None);
+        assert_eq!(Money(i64::MIN).checked_sub(Money(1)), None);
+        assert_eq!(Money(i64::MIN).checked_neg(), None);
+        assert_eq!(Money(1).checked_add(Money(2)), Some(Money(3)));
+    }
+}
@@ -0,0 +1,253 @@
+use crate::date::*;
+use crate::error::*;
+use crate::network_language::*;
+use crate::server_response::*;
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::ffi::{CStr, CString};
+
+/// Flat view of a `ServerResponse`. Fields that are not sent in every game info version are
+/// `None` if the response's version does not carry them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NetworkGameInfo {
+    /// Game info version the data was received in or will be sent in
+    pub version: u8,
+    pub server_name: CString,
+    pub server_revision: CString,
+    /// Not sent since version 6
+    pub server_lang: Option<NetworkLanguage>,
+    pub use_password: bool,
+    pub clients_max: u8,
+    pub clients_on: u8,
+    pub spectators_on: u8,
+    /// Not sent since version 6
+    pub map_name: Option<CString>,
+    pub map_width: u16,
+    pub map_height: u16,
+    pub map_set: Landscape,
+    pub dedicated: bool,
+    /// Sent since version 2
+    pub max_companies: Option<u8>,
+    /// Sent since version 2
+    pub current_companies: Option<u8>,
+    /// Sent since version 2
+    pub max_spectators: Option<u8>,
+    /// Sent as days since 1920 before version 3
+    pub game_date: OpenTTDDate,
+    /// Sent as days since 1920 before version 3
+    pub start_date: OpenTTDDate,
+    /// Sent since version 4
+    pub active_newgrf: Option<HashMap<u32, NewGRFHash>>,
+    /// Sent since version 5
+    pub gamescript_version: Option<i32>,
+    /// Sent since version 5
+    pub gamescript_name: Option<CString>,
+    /// Sent since version 6
+    pub newgrf_serialisation: Option<NewGRFSerialisation>,
+    /// Sent since version 7
+    pub ticks_playing: Option<u64>,
+}
+
+impl NetworkGameInfo {
+    /// Active NewGRFs, empty if the version does not carry them
+    pub fn newgrfs(&self) -> impl Iterator<Item = (u32, &NewGRFHash)> {
+        self.active_newgrf
+            .iter()
+            .flat_map(|newgrfs| newgrfs.iter().map(|(id, hash)| (*id, hash)))
+    }
+
+    /// Name of a NewGRF, if the server sent NewGRF names
+    pub fn newgrf_name(&self, id: u32) -> Option<&CStr> {
+        match self.newgrf_serialisation {
+            Some(NewGRFSerialisation::GrfIdMd5Name(ref names)) => {
+                names.get(&id).map(CString::as_c_str)
+            }
+            _ => None,
+        }
+    }
+
+    /// GameScript version and name, `None` if no GameScript is running or the version does not
+    /// carry it
+    pub fn gamescript(&self) -> Option<(i32, &CStr)> {
+        match (self.gamescript_version, self.gamescript_name.as_ref()) {
+            (Some(version), Some(name)) if version != -1 => Some((version, name.as_c_str())),
+            _ => None,
+        }
+    }
+
+    /// Number of companies that can still be started, if the version carries company counts
+    pub fn companies_free(&self) -> Option<u8> {
+        Some(self.max_companies?.saturating_sub(self.current_companies?))
+    }
+
+    /// Number of clients that can still join
+    pub fn clients_free(&self) -> u8 {
+        self.clients_max.saturating_sub(self.clients_on)
+    }
+}
+
+impl From<ServerResponse> for NetworkGameInfo {
+    fn from(v: ServerResponse) -> Self {
+        let version = u8::from(&v.protocol_ver);
+        let (server_lang, map_name) = if ServerResponse::sends_lang_and_map_name(version) {
+            (Some(v.server_lang), Some(v.map_name))
+        } else {
+            (None, None)
+        };
+        let v3 = v.protocol_ver.dates().clone();
+        let ProtocolVerParts {
+            v2, v4, v5, v6, v7, ..
+        } = v.protocol_ver.into_parts();
+
+        NetworkGameInfo {
+            version,
+            server_name: v.server_name,
+            server_revision: v.server_revision,
+            server_lang,
+            use_password: v.use_password,
+            clients_max: v.clients_max,
+            clients_on: v.clients_on,
+            spectators_on: v.spectators_on,
+            map_name,
+            map_width: v.map_width,
+            map_height: v.map_height,
+            map_set: v.map_set,
+            dedicated: v.dedicated,
+            max_companies: v2.as_ref().map(|v2| v2.max_companies),
+            current_companies: v2.as_ref().map(|v2| v2.current_companies),
+            max_spectators: v2.as_ref().map(|v2| v2.max_spectators),
+            game_date: v3.game_date,
+            start_date: v3.start_date,
+            active_newgrf: v4.map(|v4| v4.active_newgrf),
+            gamescript_version: v5.as_ref().map(|v5| v5.gamescript_version),
+            gamescript_name: v5.map(|v5| v5.gamescript_name),
+            newgrf_serialisation: v6.map(|v6| v6.newgrf_serialisation),
+            ticks_playing: v7.map(|v7| v7.ticks_playing),
+        }
+    }
+}
+
+impl TryFrom<NetworkGameInfo> for ServerResponse {
+    type Error = Error;
+
+    /// Fields missing for `version` are filled with the defaults of their version block, fields
+    /// `version` does not carry are ignored
+    fn try_from(v: NetworkGameInfo) -> Result<Self, Error> {
+        let v2 = V2Data {
+            max_companies: v.max_companies.unwrap_or_default(),
+            current_companies: v.current_companies.unwrap_or_default(),
+            max_spectators: v.max_spectators.unwrap_or_default(),
+        };
+        let v3 = V3Data {
+            game_date: v.game_date,
+            start_date: v.start_date,
+        };
+        let v4 = V4Data {
+            active_newgrf: v.active_newgrf.unwrap_or_default(),
+        };
+        let v5_default = V5Data::default();
+        let v5 = V5Data {
+            gamescript_version: v
+                .gamescript_version
+                .unwrap_or(v5_default.gamescript_version),
+            gamescript_name: v.gamescript_name.unwrap_or(v5_default.gamescript_name),
+        };
+        let v6 = V6Data {
+            newgrf_serialisation: v.newgrf_serialisation.unwrap_or_default(),
+        };
+        let v7 = V7Data {
+            ticks_playing: v.ticks_playing.unwrap_or_default(),
+        };
+
+        let protocol_ver = ProtocolVer::from_parts(v.version, v2, v3, v4, v5, v6, v7)?;
+
+        let mut response = ServerResponse {
+            protocol_ver,
+            server_name: v.server_name,
+            server_revision: v.server_revision,
+            server_lang: v.server_lang.unwrap_or_default(),
+            use_password: v.use_password,
+            clients_max: v.clients_max,
+            clients_on: v.clients_on,
+            spectators_on: v.spectators_on,
+            map_name: v.map_name.unwrap_or_default(),
+            map_width: v.map_width,
+            map_height: v.map_height,
+            map_set: v.map_set,
+            dedicated: v.dedicated,
+        };
+        response.take_unsent_lang_and_map_name();
+
+        Ok(response)
+    }
+}
+
+impl ServerResponse {
+    /// Flat view of the response, see `NetworkGameInfo`
+    pub fn info(&self) -> NetworkGameInfo {
+        self.clone().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_network_game_info() {
+        for version in 1..=NETWORK_GAME_INFO_VERSION {
+            let srv = ServerResponse::builder()
+                .version(version)
+                .server_name(CString::new("Test Server").unwrap())
+                .server_lang(NetworkLanguage::Finnish)
+                .map_name(CString::new("Random Map").unwrap())
+                .current_companies(3)
+                .named_newgrf(
+                    0x4D47_0101,
+                    NewGRFHash([0xAA; 16]),
+                    CString::new("OpenGFX").unwrap(),
+                )
+                .gamescript(2, CString::new("Busy Bee").unwrap())
+                .ticks_playing(74)
+                .build()
+                .unwrap();
+
+            let info = srv.info();
+            assert_eq!(info.version, version);
+            assert_eq!(info.current_companies.is_some(), version >= 2);
+            assert_eq!(info.newgrfs().count(), if version >= 4 { 1 } else { 0 });
+            assert_eq!(info.server_lang.is_some(), version < 6);
+            assert_eq!(ServerResponse::try_from(info).unwrap(), srv);
+        }
+
+        let info = ServerResponse::builder()
+            .named_newgrf(
+                0x4D47_0101,
+                NewGRFHash([0xAA; 16]),
+                CString::new("OpenGFX").unwrap(),
+            )
+            .gamescript(2, CString::new("Busy Bee").unwrap())
+            .build()
+            .unwrap()
+            .info();
+        assert_eq!(
+            info.newgrf_name(0x4D47_0101).unwrap().to_str(),
+            Ok("OpenGFX")
+        );
+        assert_eq!(info.gamescript().unwrap().0, 2);
+        assert_eq!(info.companies_free(), Some(15));
+
+        let mut info = info;
+        info.version = 0;
+        assert_eq!(
+            ServerResponse::try_from(info.clone()).unwrap_err(),
+            Error::UnknownType {
+                kind: "game info version",
+                value: 0
+            }
+        );
+        info.version = NETWORK_GAME_INFO_VERSION + 1;
+        assert!(ServerResponse::try_from(info).is_err());
+    }
+}
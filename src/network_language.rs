@@ -0,0 +1,184 @@
+use std::fmt;
+
+/// Language a server advertises to the server list
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum NetworkLanguage {
+    #[default]
+    Any,
+    English,
+    German,
+    French,
+    Brazilian,
+    Bulgarian,
+    Chinese,
+    Czech,
+    Danish,
+    Dutch,
+    Esperanto,
+    Finnish,
+    Hungarian,
+    Icelandic,
+    Italian,
+    Japanese,
+    Korean,
+    Lithuanian,
+    Norwegian,
+    Polish,
+    Portuguese,
+    Romanian,
+    Russian,
+    Slovak,
+    Slovenian,
+    Spanish,
+    Swedish,
+    Turkish,
+    Ukrainian,
+    Afrikaans,
+    Croatian,
+    Catalan,
+    Estonian,
+    Galician,
+    Greek,
+    Latvian,
+    Unknown(u8),
+}
+
+/// Language, ISO 639-1 code, English and native name
+type LanguageEntry = (NetworkLanguage, &'static str, &'static str, &'static str);
+
+/// OpenTTD's network language list, indexed by wire value
+const LANGUAGES: [LanguageEntry; 36] = [
+    (NetworkLanguage::Any, "", "Any", "Any"),
+    (NetworkLanguage::English, "en", "English", "English"),
+    (NetworkLanguage::German, "de", "German", "Deutsch"),
+    (NetworkLanguage::French, "fr", "French", "Français"),
+    (
+        NetworkLanguage::Brazilian,
+        "pt-BR",
+        "Brazilian Portuguese",
+        "Português (Brasil)",
+    ),
+    (NetworkLanguage::Bulgarian, "bg", "Bulgarian", "Български"),
+    (NetworkLanguage::Chinese, "zh", "Chinese", "中文"),
+    (NetworkLanguage::Czech, "cs", "Czech", "Čeština"),
+    (NetworkLanguage::Danish, "da", "Danish", "Dansk"),
+    (NetworkLanguage::Dutch, "nl", "Dutch", "Nederlands"),
+    (NetworkLanguage::Esperanto, "eo", "Esperanto", "Esperanto"),
+    (NetworkLanguage::Finnish, "fi", "Finnish", "Suomi"),
+    (NetworkLanguage::Hungarian, "hu", "Hungarian", "Magyar"),
+    (NetworkLanguage::Icelandic, "is", "Icelandic", "Íslenska"),
+    (NetworkLanguage::Italian, "it", "Italian", "Italiano"),
+    (NetworkLanguage::Japanese, "ja", "Japanese", "日本語"),
+    (NetworkLanguage::Korean, "ko", "Korean", "한국어"),
+    (NetworkLanguage::Lithuanian, "lt", "Lithuanian", "Lietuvių"),
+    (NetworkLanguage::Norwegian, "no", "Norwegian", "Norsk"),
+    (NetworkLanguage::Polish, "pl", "Polish", "Polski"),
+    (NetworkLanguage::Portuguese, "pt", "Portuguese", "Português"),
+    (NetworkLanguage::Romanian, "ro", "Romanian", "Română"),
+    (NetworkLanguage::Russian, "ru", "Russian", "Русский"),
+    (NetworkLanguage::Slovak, "sk", "Slovak", "Slovenčina"),
+    (NetworkLanguage::Slovenian, "sl", "Slovenian", "Slovenščina"),
+    (NetworkLanguage::Spanish, "es", "Spanish", "Español"),
+    (NetworkLanguage::Swedish, "sv", "Swedish", "Svenska"),
+    (NetworkLanguage::Turkish, "tr", "Turkish", "Türkçe"),
+    (NetworkLanguage::Ukrainian, "uk", "Ukrainian", "Українська"),
+    (NetworkLanguage::Afrikaans, "af", "Afrikaans", "Afrikaans"),
+    (NetworkLanguage::Croatian, "hr", "Croatian", "Hrvatski"),
+    (NetworkLanguage::Catalan, "ca", "Catalan", "Català"),
+    (NetworkLanguage::Estonian, "et", "Estonian", "Eesti"),
+    (NetworkLanguage::Galician, "gl", "Galician", "Galego"),
+    (NetworkLanguage::Greek, "el", "Greek", "Ελληνικά"),
+    (NetworkLanguage::Latvian, "lv", "Latvian", "Latviešu"),
+];
+
+impl From<u8> for NetworkLanguage {
+    fn from(v: u8) -> Self {
+        LANGUAGES
+            .get(v as usize)
+            .map(|entry| entry.0)
+            .unwrap_or(NetworkLanguage::Unknown(v))
+    }
+}
+
+impl From<NetworkLanguage> for u8 {
+    fn from(v: NetworkLanguage) -> Self {
+        match v {
+            NetworkLanguage::Unknown(other) => other,
+            _ => LANGUAGES.iter().position(|entry| entry.0 == v).unwrap() as u8,
+        }
+    }
+}
+
+impl NetworkLanguage {
+    fn entry(&self) -> Option<&'static LanguageEntry> {
+        LANGUAGES.iter().find(|entry| entry.0 == *self)
+    }
+
+    /// All languages known to OpenTTD, in wire order
+    pub fn all() -> impl Iterator<Item = NetworkLanguage> {
+        LANGUAGES.iter().map(|entry| entry.0)
+    }
+
+    /// Look up a language by its ISO 639-1 code, e.g. `de`. Brazilian Portuguese is `pt-BR`.
+    pub fn from_iso639(code: &str) -> Option<Self> {
+        let code = code.replace('_', "-");
+        LANGUAGES
+            .iter()
+            .skip(1)
+            .find(|entry| entry.1.eq_ignore_ascii_case(&code))
+            .map(|entry| entry.0)
+    }
+
+    /// ISO 639-1 code, `None` for `Any` and unknown languages
+    pub fn iso639(&self) -> Option<&'static str> {
+        match self.entry() {
+            Some(entry) if !entry.1.is_empty() => Some(entry.1),
+            _ => None,
+        }
+    }
+
+    /// Name of the language in English
+    pub fn english_name(&self) -> &'static str {
+        self.entry().map(|entry| entry.2).unwrap_or("Unknown")
+    }
+
+    /// Name of the language in the language itself
+    pub fn native_name(&self) -> &'static str {
+        self.entry().map(|entry| entry.3).unwrap_or("Unknown")
+    }
+}
+
+impl fmt::Display for NetworkLanguage {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            NetworkLanguage::Unknown(v) => write!(fmt, "Unknown ({})", v),
+            _ => write!(fmt, "{}", self.english_name()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_network_language() {
+        for v in 0..=255u8 {
+            assert_eq!(u8::from(NetworkLanguage::from(v)), v);
+        }
+
+        assert_eq!(NetworkLanguage::from(22), NetworkLanguage::Russian);
+        assert_eq!(NetworkLanguage::from(36), NetworkLanguage::Unknown(36));
+        assert_eq!(NetworkLanguage::Russian.iso639(), Some("ru"));
+        assert_eq!(NetworkLanguage::German.native_name(), "Deutsch");
+        assert_eq!(NetworkLanguage::Any.iso639(), None);
+        assert_eq!(
+            NetworkLanguage::from_iso639("pt_BR"),
+            Some(NetworkLanguage::Brazilian)
+        );
+        assert_eq!(
+            NetworkLanguage::from_iso639("PT"),
+            Some(NetworkLanguage::Portuguese)
+        );
+    }
+}
@@ -0,0 +1,203 @@
+use crate::error::*;
+use crate::master_response_list::SEND_MTU;
+use crate::server_detail_info::*;
+use crate::server_response::*;
+use crate::util::*;
+use crate::{Packet, PacketType};
+
+use std::fmt;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+/// Port OpenTTD servers listen on by default
+pub const NETWORK_DEFAULT_PORT: u16 = 3979;
+
+/// Failure to query a server
+#[derive(Debug)]
+pub enum QueryError {
+    Io(io::Error),
+    /// The server only replied with packets that could not be parsed
+    Parse(Error),
+    /// No reply arrived within the timeout, after all retries
+    Timeout,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            QueryError::Io(ref e) => write!(fmt, "I/O error: {}", e),
+            QueryError::Parse(ref e) => write!(fmt, "invalid reply: {}", e),
+            QueryError::Timeout => write!(fmt, "no reply from server"),
+        }
+    }
+}
+
+impl std::error::Error for QueryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            QueryError::Io(ref e) => Some(e),
+            QueryError::Parse(ref e) => Some(e),
+            QueryError::Timeout => None,
+        }
+    }
+}
+
+impl From<io::Error> for QueryError {
+    fn from(e: io::Error) -> Self {
+        QueryError::Io(e)
+    }
+}
+
+/// Blocking client querying single servers for game and company information
+#[derive(Debug)]
+pub struct QueryClient {
+    socket: UdpSocket,
+    timeout: Duration,
+    retries: u32,
+}
+
+impl QueryClient {
+    /// Bind to an ephemeral IPv4 port. Use `bind` to query IPv6 servers.
+    pub fn new() -> io::Result<Self> {
+        Self::bind("0.0.0.0:0")
+    }
+
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        Ok(Self {
+            socket: UdpSocket::bind(addr)?,
+            timeout: Duration::from_secs(2),
+            retries: 2,
+        })
+    }
+
+    /// Time to wait for a reply before resending the request, defaults to 2 seconds
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Number of times the request is resent after a timeout, defaults to 2
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Wait until `deadline` for a packet of the expected type from `server`.
+    /// Datagrams from other addresses and packets of other types are ignored, as are packets
+    /// that cannot be parsed, which are recorded in `parse_error`.
+    fn receive(
+        &self,
+        server: SocketAddr,
+        expected: PacketType,
+        deadline: Instant,
+        parse_error: &mut Option<Error>,
+    ) -> io::Result<Option<Packet>> {
+        let mut buf = [0; SEND_MTU];
+        while Instant::now() < deadline {
+            let (len, from) = match recv_until(&self.socket, &mut buf, deadline)? {
+                Some(v) => v,
+                None => continue,
+            };
+            if from != server {
+                continue;
+            }
+
+            match Packet::from_incoming_bytes(&buf[..len]) {
+                Ok(packet) if packet.pkt_type() == expected => return Ok(Some(packet)),
+                Ok(_) => {}
+                Err(e) => *parse_error = Some(e),
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Send `request` to `server` and wait for a reply of type `expected`, resending on timeout.
+    /// Fails with `QueryError::Parse` if the server only sent replies that could not be parsed.
+    pub fn query(
+        &self,
+        server: SocketAddr,
+        request: &Packet,
+        expected: PacketType,
+    ) -> Result<Packet, QueryError> {
+        let bytes = request.to_bytes()?;
+        let mut parse_error = None;
+        for _ in 0..=self.retries {
+            self.socket.send_to(&bytes, server)?;
+            let deadline = Instant::now() + self.timeout;
+            if let Some(packet) = self.receive(server, expected, deadline, &mut parse_error)? {
+                return Ok(packet);
+            }
+        }
+
+        Err(parse_error.map_or(QueryError::Timeout, QueryError::Parse))
+    }
+
+    /// Ask the server for its game information
+    pub fn server_response(&self, server: SocketAddr) -> Result<ServerResponse, QueryError> {
+        match self.query(
+            server,
+            &Packet::ClientFindServer,
+            PacketType::ServerResponse,
+        )? {
+            Packet::ServerResponse(data) => Ok(data),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Ask the server for its companies
+    pub fn server_detail_info(&self, server: SocketAddr) -> Result<ServerDetailInfo, QueryError> {
+        match self.query(
+            server,
+            &Packet::ClientDetailInfo,
+            PacketType::ServerDetailInfo,
+        )? {
+            Packet::ServerDetailInfo(data) => Ok(data),
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::StandIn;
+
+    #[test]
+    fn test_query_server_response() {
+        // Garbage in reply to the first request forces a retry
+        let server = StandIn::new("Test Server").ignore(1).garbage().spawn();
+
+        let client = QueryClient::bind("127.0.0.1:0")
+            .unwrap()
+            .timeout(Duration::from_millis(200));
+
+        // Noise from another address must not be taken for the reply
+        let stranger = UdpSocket::bind("127.0.0.1:0").unwrap();
+        stranger
+            .send_to(&[3, 0, 1], client.local_addr().unwrap())
+            .unwrap();
+
+        let response = client.server_response(server).unwrap();
+        assert_eq!(response.server_name.to_str(), Ok("Test Server"));
+
+        let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let client = client.retries(0);
+        match client.server_detail_info(silent.local_addr().unwrap()) {
+            Err(QueryError::Timeout) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        // Nothing but garbage before the last attempt ends
+        let garbage = StandIn::new("Garbage").ignore(usize::MAX).garbage().spawn();
+        match client.server_response(garbage) {
+            Err(QueryError::Parse(Error::UnknownType { kind: "packet", .. })) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+}
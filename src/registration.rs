@@ -0,0 +1,120 @@
+use crate::server_register::*;
+use crate::Packet;
+
+use std::ffi::CString;
+
+/// Welcome message expected by the master server in `ServerRegister` packets
+pub const NETWORK_MASTER_SERVER_WELCOME_MESSAGE: &str = "OpenTTDRegister";
+/// Master server protocol version spoken by this crate
+pub const NETWORK_MASTER_SERVER_VERSION: u8 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RegistrationState {
+    /// Nothing has been sent yet
+    Idle,
+    /// Registered without a session key, waiting for the master to hand one out
+    AwaitingSessionKey,
+    /// Registered with a session key, waiting for the master to acknowledge
+    AwaitingAck,
+    /// The master server has acknowledged the registration
+    Registered,
+}
+
+/// Transport-agnostic state machine for advertising a server to the master server.
+///
+/// Feed every packet received from the master server into `handle` and send whatever it returns.
+/// `start` (re)sends the registration and can also be used for retries on timeout.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Registration {
+    pub port: u16,
+    pub session_key: Option<u64>,
+    pub state: RegistrationState,
+}
+
+impl Registration {
+    pub fn new(port: u16) -> Self {
+        Self {
+            port,
+            session_key: None,
+            state: RegistrationState::Idle,
+        }
+    }
+
+    /// Reuse a session key obtained during an earlier registration
+    pub fn with_session_key(mut self, session_key: u64) -> Self {
+        self.session_key = Some(session_key);
+        self
+    }
+
+    fn register_packet(&self) -> Packet {
+        Packet::ServerRegister(ServerRegistrationData {
+            welcome_message: CString::new(NETWORK_MASTER_SERVER_WELCOME_MESSAGE).unwrap(),
+            server_version: NETWORK_MASTER_SERVER_VERSION,
+            port: self.port,
+            session_key: self.session_key.unwrap_or(0),
+        })
+    }
+
+    /// Begin (or retry) registration, returning the packet to send to the master server
+    pub fn start(&mut self) -> Packet {
+        self.state = if self.session_key.is_some() {
+            RegistrationState::AwaitingAck
+        } else {
+            RegistrationState::AwaitingSessionKey
+        };
+        self.register_packet()
+    }
+
+    /// Process a packet from the master server, returning a packet to send in reply if any
+    pub fn handle(&mut self, pkt: &Packet) -> Option<Packet> {
+        match (self.state, pkt) {
+            (RegistrationState::Idle, _) | (RegistrationState::Registered, _) => None,
+            (_, Packet::MasterSessionKey(data)) => {
+                self.session_key = Some(data.session_key);
+                self.state = RegistrationState::AwaitingAck;
+                Some(self.register_packet())
+            }
+            (_, Packet::MasterAckRegister) => {
+                self.state = RegistrationState::Registered;
+                None
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_registered(&self) -> bool {
+        self.state == RegistrationState::Registered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MasterSessionKeyData;
+
+    #[test]
+    fn test_registration_handshake() {
+        let mut reg = Registration::new(3979);
+
+        match reg.start() {
+            Packet::ServerRegister(data) => assert_eq!(data.session_key, 0),
+            other => panic!("unexpected packet {:?}", other),
+        }
+        assert_eq!(reg.state, RegistrationState::AwaitingSessionKey);
+
+        let reply = reg.handle(&Packet::MasterSessionKey(MasterSessionKeyData {
+            session_key: 0xdead_beef,
+        }));
+        match reply {
+            Some(Packet::ServerRegister(data)) => {
+                assert_eq!(data.session_key, 0xdead_beef);
+                assert_eq!(data.port, 3979);
+            }
+            other => panic!("unexpected reply {:?}", other),
+        }
+        assert_eq!(reg.state, RegistrationState::AwaitingAck);
+
+        assert_eq!(reg.handle(&Packet::MasterAckRegister), None);
+        assert!(reg.is_registered());
+    }
+}
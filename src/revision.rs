@@ -0,0 +1,343 @@
+use crate::limits::*;
+use crate::server_response::*;
+use crate::text::*;
+
+use chrono::NaiveDate;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Length of the git hash suffix OpenTTD compares, including the leading `-`
+pub const GITHASH_SUFFIX_LEN: usize = 12;
+
+/// First `len` bytes of the string, or all of it if it is shorter
+fn byte_prefix(s: &str, len: usize) -> &[u8] {
+    &s.as_bytes()[..s.len().min(len)]
+}
+
+/// What kind of build a revision string describes
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RevisionKind {
+    /// Tagged release, e.g. `14.1`
+    Release,
+    /// Release candidate, e.g. `14.0-RC1`
+    ReleaseCandidate(u32),
+    /// Beta, e.g. `14.0-beta2`
+    Beta(u32),
+    /// Development build, e.g. `20240101-master-g1234abcdef`
+    Nightly {
+        date: NaiveDate,
+        branch: String,
+        hash: String,
+    },
+    /// Revision string in a format not known to this crate
+    Unknown,
+}
+
+/// OpenTTD revision string split into its parts
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Revision {
+    /// Revision string as sent by the server
+    pub raw: String,
+    /// Name of the patch pack, e.g. `jgrpp` for `jgrpp-0.57.1`
+    pub patchpack: Option<String>,
+    /// Version number components, empty for nightlies
+    pub version: Vec<u32>,
+    pub kind: RevisionKind,
+    /// Anything following a tagged version, e.g. the `git describe` part of patch pack builds
+    pub suffix: Option<String>,
+    /// Built from a tree with local changes
+    pub modified: bool,
+}
+
+fn parse_version(s: &str) -> Option<Vec<u32>> {
+    s.split('.').map(|part| part.parse().ok()).collect()
+}
+
+/// Parse `-RC1` or `-beta2`, returning the pre-release kind and what follows it
+fn parse_prerelease(s: &str) -> Option<(RevisionKind, &str)> {
+    let s = s.strip_prefix('-')?;
+    let starts_with = |prefix: &str| {
+        s.get(..prefix.len())
+            .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+    };
+    let (kind, rest): (fn(u32) -> RevisionKind, &str) = if starts_with("rc") {
+        (RevisionKind::ReleaseCandidate, &s[2..])
+    } else if starts_with("beta") {
+        (RevisionKind::Beta, &s[4..])
+    } else {
+        return None;
+    };
+
+    let digits = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    let number = rest[..digits].parse().ok()?;
+
+    Some((kind(number), &rest[digits..]))
+}
+
+/// Parse `YYYYMMDD-branch-gHASH`. The hash prefix is `g` for clean, `m` for modified and `u`
+/// for unknown trees.
+fn parse_nightly(s: &str) -> Option<(RevisionKind, bool)> {
+    let bytes = s.as_bytes();
+    if bytes.len() < 9 || !bytes[..8].iter().all(u8::is_ascii_digit) || bytes[8] != b'-' {
+        return None;
+    }
+    let date = NaiveDate::parse_from_str(&s[..8], "%Y%m%d").ok()?;
+
+    let rest = &s[9..];
+    let hash_start = rest.rfind('-')?;
+    let branch = &rest[..hash_start];
+    let hash = &rest[hash_start + 1..];
+    let modified = match hash.chars().next()? {
+        'g' | 'u' => false,
+        'm' => true,
+        _ => return None,
+    };
+
+    Some((
+        RevisionKind::Nightly {
+            date,
+            branch: branch.to_string(),
+            hash: hash[1..].to_string(),
+        },
+        modified,
+    ))
+}
+
+impl Revision {
+    /// Split a revision string. Strings in unknown formats are kept as `RevisionKind::Unknown`.
+    pub fn parse(raw: &str) -> Revision {
+        let mut revision = Revision {
+            raw: raw.to_string(),
+            patchpack: None,
+            version: vec![],
+            kind: RevisionKind::Unknown,
+            suffix: None,
+            modified: false,
+        };
+
+        let mut s = raw;
+        // Revisions built from modified trees used to end with an `M`
+        if let Some(stripped) = s.strip_suffix('M') {
+            revision.modified = true;
+            s = stripped;
+        }
+
+        if let Some((kind, modified)) = parse_nightly(s) {
+            revision.kind = kind;
+            revision.modified |= modified;
+            return revision;
+        }
+
+        // Patch packs prefix the version with their name, e.g. `jgrpp-0.57.1`
+        if let Some(pos) = s.find('-') {
+            let (name, rest) = (&s[..pos], &s[pos + 1..]);
+            if !name.is_empty()
+                && name.chars().all(|c| c.is_ascii_alphabetic())
+                && rest.starts_with(|c: char| c.is_ascii_digit())
+            {
+                revision.patchpack = Some(name.to_string());
+                s = rest;
+            }
+        }
+
+        let version_end = s
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(s.len());
+        let version = match parse_version(&s[..version_end]) {
+            Some(version) => version,
+            None => return revision,
+        };
+        s = &s[version_end..];
+
+        revision.version = version;
+        revision.kind = match parse_prerelease(s) {
+            Some((kind, rest)) => {
+                s = rest;
+                kind
+            }
+            None => RevisionKind::Release,
+        };
+        if !s.is_empty() {
+            revision.suffix = Some(s.trim_start_matches('-').to_string());
+        }
+
+        revision
+    }
+
+    /// Whether this is an unmodified release, beta or release candidate build
+    pub fn is_tagged(&self) -> bool {
+        match self.kind {
+            RevisionKind::Release | RevisionKind::ReleaseCandidate(_) | RevisionKind::Beta(_) => {
+                !self.modified && self.suffix.is_none()
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether a client running this revision can join a server running `other`, following
+    /// OpenTTD's rule: tagged builds need an exact match, other builds the same git hash. Like
+    /// OpenTTD, only the first `NETWORK_REVISION_LENGTH - 1` bytes of the revisions and the first
+    /// `GITHASH_SUFFIX_LEN` bytes of the hash suffix are compared.
+    pub fn is_network_compatible_with(&self, other: &Revision) -> bool {
+        if byte_prefix(&self.raw, NETWORK_REVISION_LENGTH - 1)
+            == byte_prefix(&other.raw, NETWORK_REVISION_LENGTH - 1)
+        {
+            return true;
+        }
+        if self.is_tagged() {
+            return false;
+        }
+
+        let hash = |raw: &str| {
+            raw.rfind('-')
+                .map(|pos| byte_prefix(&raw[pos..], GITHASH_SUFFIX_LEN).to_vec())
+        };
+        match (hash(&self.raw), hash(&other.raw)) {
+            (Some(ours), Some(theirs)) => ours == theirs,
+            _ => false,
+        }
+    }
+
+    /// Key that sorts patch packs apart, releases by version with pre-releases before the
+    /// final release, and nightlies after all numbered versions by date
+    fn sort_key(&self) -> impl Ord + '_ {
+        let (class, stage, number, date) = match self.kind {
+            RevisionKind::Unknown => (0, 0, 0, None),
+            RevisionKind::Beta(n) => (1, 0, n, None),
+            RevisionKind::ReleaseCandidate(n) => (1, 1, n, None),
+            RevisionKind::Release => (1, 2, 0, None),
+            RevisionKind::Nightly { date, .. } => (2, 0, 0, Some(date)),
+        };
+
+        (
+            &self.patchpack,
+            class,
+            &self.version,
+            stage,
+            number,
+            date,
+            &self.raw,
+        )
+    }
+}
+
+impl PartialOrd for Revision {
+    fn partial_cmp(&self, other: &Revision) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Revision {
+    fn cmp(&self, other: &Revision) -> Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+}
+
+impl fmt::Display for Revision {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", self.raw)
+    }
+}
+
+impl ServerResponse {
+    /// Parsed `server_revision`
+    pub fn revision(&self) -> Revision {
+        Revision::parse(&self.server_revision.text_lossy())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_revision() {
+        let rev = Revision::parse("1.5.3");
+        assert_eq!(rev.version, vec![1, 5, 3]);
+        assert_eq!(rev.kind, RevisionKind::Release);
+        assert!(rev.is_tagged());
+
+        let rev = Revision::parse("14.0-RC1");
+        assert_eq!(rev.version, vec![14, 0]);
+        assert_eq!(rev.kind, RevisionKind::ReleaseCandidate(1));
+
+        let rev = Revision::parse("1.10.0-beta2M");
+        assert_eq!(rev.kind, RevisionKind::Beta(2));
+        assert!(rev.modified);
+        assert!(!rev.is_tagged());
+
+        let rev = Revision::parse("20240101-master-m1a2b3c4d5e");
+        assert_eq!(
+            rev.kind,
+            RevisionKind::Nightly {
+                date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+                branch: "master".to_string(),
+                hash: "1a2b3c4d5e".to_string(),
+            }
+        );
+        assert!(rev.modified);
+
+        let rev = Revision::parse("jgrpp-0.57.1-3-gabcdef");
+        assert_eq!(rev.patchpack.as_deref(), Some("jgrpp"));
+        assert_eq!(rev.version, vec![0, 57, 1]);
+        assert_eq!(rev.suffix.as_deref(), Some("3-gabcdef"));
+
+        assert_eq!(Revision::parse("norev000").kind, RevisionKind::Unknown);
+        assert_eq!(Revision::parse("1.0-Ü").suffix.as_deref(), Some("Ü"));
+        assert_eq!(Revision::parse("2024010Ü").version, vec![2024010]);
+    }
+
+    #[test]
+    fn test_revision_order() {
+        let mut revisions: Vec<_> = [
+            "20240101-master-g1a2b3c4d5e",
+            "14.1",
+            "14.0-RC1",
+            "13.4",
+            "14.0",
+            "14.0-beta3",
+            "jgrpp-0.57.1",
+        ]
+        .iter()
+        .map(|raw| Revision::parse(raw))
+        .collect();
+        revisions.sort();
+
+        let sorted: Vec<_> = revisions.iter().map(|rev| rev.raw.as_str()).collect();
+        assert_eq!(
+            sorted,
+            vec![
+                "13.4",
+                "14.0-beta3",
+                "14.0-RC1",
+                "14.0",
+                "14.1",
+                "20240101-master-g1a2b3c4d5e",
+                "jgrpp-0.57.1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_network_compatible() {
+        let release = Revision::parse("14.1");
+        assert!(release.is_network_compatible_with(&Revision::parse("14.1")));
+        assert!(!release.is_network_compatible_with(&Revision::parse("14.0")));
+
+        let nightly = Revision::parse("20240101-master-g1a2b3c4d5e");
+        assert!(nightly.is_network_compatible_with(&Revision::parse("20240101-fix-g1a2b3c4d5e")));
+        assert!(
+            !nightly.is_network_compatible_with(&Revision::parse("20240101-master-m1a2b3c4d5e"))
+        );
+
+        // Bytes past the wire limit and past the compared part of the hash are ignored
+        let long = Revision::parse("jgrpp-0.57.1-with-a-very-long-suffix-1");
+        assert!(long.is_network_compatible_with(&Revision::parse(
+            "jgrpp-0.57.1-with-a-very-long-suffix-2"
+        )));
+        let nightly = Revision::parse("20240101-master-g1a2b3c4d5e6f");
+        assert!(nightly.is_network_compatible_with(&Revision::parse("20240102-fix-g1a2b3c4d5e00")));
+    }
+}
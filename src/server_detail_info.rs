@@ -1,18 +1,202 @@
+use crate::date::*;
+use crate::error::*;
+use crate::limits::*;
+use crate::money::*;
 use crate::util::*;
 
 use byteorder::{LittleEndian, WriteBytesExt};
 use nom::{self, number::complete::*, *};
-use std;
-use std::collections::HashMap;
 use std::ffi::CString;
+use std::fmt;
+use std::ops::{Index, IndexMut};
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
-pub enum NetworkVehicleType {
-    Train,
-    Lorry,
-    Bus,
-    Plane,
-    Ship,
+/// Newest company info version this crate can encode
+pub const NETWORK_COMPANY_INFO_VERSION: u8 = 6;
+/// Oldest company info version this crate can decode
+const MIN_COMPANY_INFO_VERSION: u8 = 4;
+
+/// Declare `NetworkVehicleType`, `NetworkVehicleType::ALL` and `NETWORK_VEH_END` from a single
+/// list, so a new vehicle type only has to be added here
+macro_rules! network_vehicle_types {
+    ($($name:ident),* $(,)?) => {
+        #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+        pub enum NetworkVehicleType {
+            $($name,)*
+        }
+
+        /// Number of vehicle types counted in company info
+        pub const NETWORK_VEH_END: usize = [$(NetworkVehicleType::$name),*].len();
+
+        impl NetworkVehicleType {
+            /// All vehicle types, in wire order
+            pub const ALL: [NetworkVehicleType; NETWORK_VEH_END] =
+                [$(NetworkVehicleType::$name),*];
+        }
+    };
+}
+
+// In wire order
+network_vehicle_types!(Train, Lorry, Bus, Plane, Ship);
+
+impl NetworkVehicleType {
+    /// Position in `ALL`, which lists the variants in declaration order
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Vehicle or station count for every vehicle type
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct VehicleTypeCounts([u16; NETWORK_VEH_END]);
+
+impl VehicleTypeCounts {
+    /// Counts paired with their vehicle type, in wire order
+    pub fn iter(&self) -> impl Iterator<Item = (NetworkVehicleType, u16)> + '_ {
+        NetworkVehicleType::ALL
+            .iter()
+            .copied()
+            .zip(self.0.iter().copied())
+    }
+
+    /// Sum of all counts
+    pub fn total(&self) -> u32 {
+        self.0.iter().map(|&v| u32::from(v)).sum()
+    }
+}
+
+impl From<[u16; NETWORK_VEH_END]> for VehicleTypeCounts {
+    /// Build from counts in wire order, see `NetworkVehicleType::ALL`
+    fn from(v: [u16; NETWORK_VEH_END]) -> Self {
+        VehicleTypeCounts(v)
+    }
+}
+
+impl Index<NetworkVehicleType> for VehicleTypeCounts {
+    type Output = u16;
+
+    fn index(&self, vehicle_type: NetworkVehicleType) -> &u16 {
+        &self.0[vehicle_type.index()]
+    }
+}
+
+impl IndexMut<NetworkVehicleType> for VehicleTypeCounts {
+    fn index_mut(&mut self, vehicle_type: NetworkVehicleType) -> &mut u16 {
+        &mut self.0[vehicle_type.index()]
+    }
+}
+
+impl ByteWriter for VehicleTypeCounts {
+    fn write_pkt(&self, buf: &mut Vec<u8>) -> std::io::Result<()> {
+        for count in self.0.iter() {
+            buf.write_u16::<LittleEndian>(*count)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_vehicle_type_counts(mut buf: &[u8]) -> ParseResult<'_, VehicleTypeCounts> {
+    let mut counts = VehicleTypeCounts::default();
+    for count in counts.0.iter_mut() {
+        let (rest, v) = le_u16(buf)?;
+        *count = v;
+        buf = rest;
+    }
+
+    Ok((buf, counts))
+}
+
+/// Client entry, only sent up to company info version 4
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClientInfo {
+    pub name: CString,
+    pub unique_id: CString,
+    pub join_date: OpenTTDDate,
+}
+
+impl ClientInfo {
+    /// Shorten the name and unique ID to the lengths OpenTTD accepts
+    pub fn truncate_to_limits(&mut self) {
+        truncate_cstring(&mut self.name, NETWORK_CLIENT_NAME_LENGTH);
+        truncate_cstring(&mut self.unique_id, NETWORK_UNIQUE_ID_LENGTH);
+    }
+}
+
+impl ByteWriter for ClientInfo {
+    fn write_pkt(&self, buf: &mut Vec<u8>) -> std::io::Result<()> {
+        write_cstring(buf, &self.name, NETWORK_CLIENT_NAME_LENGTH, "client name")?;
+        write_cstring(buf, &self.unique_id, NETWORK_UNIQUE_ID_LENGTH, "unique ID")?;
+        self.join_date.write_pkt(buf)?;
+
+        Ok(())
+    }
+}
+
+named!(parse_client_info<&[u8], ClientInfo, Error>,
+    do_parse!(
+        name: read_cstring >>
+        unique_id: read_cstring >>
+        join_date: parse_date >>
+        (ClientInfo { name, unique_id, join_date })
+    )
+);
+
+/// Write clients, each preceded by a `1` byte, with a `0` byte ending the list
+fn write_client_list(clients: &[ClientInfo], buf: &mut Vec<u8>) -> std::io::Result<()> {
+    for client in clients.iter() {
+        buf.write_u8(1)?;
+        client.write_pkt(buf)?;
+    }
+    buf.write_u8(0)?;
+
+    Ok(())
+}
+
+fn parse_client_list(mut buf: &[u8]) -> ParseResult<'_, Vec<ClientInfo>> {
+    let mut clients = vec![];
+    loop {
+        let (rest, more) = le_u8(buf)?;
+        if more == 0 {
+            return Ok((rest, clients));
+        }
+
+        let (rest, client) = parse_client_info(rest)?;
+        clients.push(client);
+        buf = rest;
+    }
+}
+
+/// Highest company performance rating
+pub const SCORE_MAX: u16 = 1000;
+
+/// Company performance rating of the last quarter, from 0 to `SCORE_MAX`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PerformanceRating(pub u16);
+
+impl PerformanceRating {
+    /// Rating as a share of the maximum, from 0.0 to 1.0
+    pub fn fraction(&self) -> f32 {
+        f32::from(self.0.min(SCORE_MAX)) / f32::from(SCORE_MAX)
+    }
+
+    /// Title OpenTTD awards for the rating, e.g. in the high score table
+    pub fn title(&self) -> &'static str {
+        match self.0.min(SCORE_MAX) >> 6 {
+            0..=4 => "Businessman",
+            5..=6 => "Entrepreneur",
+            7..=8 => "Industrialist",
+            9..=10 => "Capitalist",
+            11..=12 => "Magnate",
+            13..=14 => "Mogul",
+            _ => "Tycoon of the Century",
+        }
+    }
+}
+
+impl fmt::Display for PerformanceRating {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{} ({})", self.0, self.title())
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -20,119 +204,68 @@ pub struct CompanyInfo {
     pub index: u8,
     pub name: CString,
     pub inaugurated_year: u32,
-    pub company_value: u64,
-    pub money: u64,
-    pub income: u64,
-    pub performance_history: u16,
+    pub company_value: Money,
+    pub money: Money,
+    /// Income of the last year, or of the current year if the company was founded last year
+    pub income: Money,
+    pub performance_history: PerformanceRating,
     pub has_password: bool,
-    pub num_vehicles: HashMap<NetworkVehicleType, u16>,
-    pub num_stations: HashMap<NetworkVehicleType, u16>,
+    pub num_vehicles: VehicleTypeCounts,
+    pub num_stations: VehicleTypeCounts,
+    /// Sent since company info version 6, `false` before
     pub is_ai: bool,
+    /// Clients playing as this company, only sent up to company info version 4
+    pub clients: Vec<ClientInfo>,
 }
 
 impl CompanyInfo {
-    pub fn write_pkt(&self, buf: &mut Vec<u8>) -> std::io::Result<()> {
+    /// Shorten the company and client names to the lengths OpenTTD accepts
+    pub fn truncate_to_limits(&mut self) {
+        truncate_cstring(&mut self.name, NETWORK_COMPANY_NAME_LENGTH);
+        for client in self.clients.iter_mut() {
+            client.truncate_to_limits();
+        }
+    }
+
+    /// Encode the company in the layout of the given company info version
+    pub fn write_pkt(&self, company_info_version: u8, buf: &mut Vec<u8>) -> std::io::Result<()> {
         buf.write_u8(self.index)?;
-        buf.append(&mut self.name.clone().into_bytes_with_nul());
+        write_cstring(buf, &self.name, NETWORK_COMPANY_NAME_LENGTH, "company name")?;
         buf.write_u32::<LittleEndian>(self.inaugurated_year)?;
-        buf.write_u64::<LittleEndian>(self.company_value)?;
-        buf.write_u64::<LittleEndian>(self.money)?;
-        buf.write_u64::<LittleEndian>(self.income)?;
-        buf.write_u16::<LittleEndian>(self.performance_history)?;
+        buf.write_i64::<LittleEndian>(self.company_value.0)?;
+        buf.write_i64::<LittleEndian>(self.money.0)?;
+        buf.write_i64::<LittleEndian>(self.income.0)?;
+        buf.write_u16::<LittleEndian>(self.performance_history.0)?;
         buf.write_u8(if self.has_password { 1 } else { 0 })?;
 
-        buf.write_u16::<LittleEndian>(
-            *self
-                .num_vehicles
-                .get(&NetworkVehicleType::Train)
-                .unwrap_or(&0),
-        )?;
-        buf.write_u16::<LittleEndian>(
-            *self
-                .num_vehicles
-                .get(&NetworkVehicleType::Lorry)
-                .unwrap_or(&0),
-        )?;
-        buf.write_u16::<LittleEndian>(
-            *self
-                .num_vehicles
-                .get(&NetworkVehicleType::Bus)
-                .unwrap_or(&0),
-        )?;
-        buf.write_u16::<LittleEndian>(
-            *self
-                .num_vehicles
-                .get(&NetworkVehicleType::Plane)
-                .unwrap_or(&0),
-        )?;
-        buf.write_u16::<LittleEndian>(
-            *self
-                .num_vehicles
-                .get(&NetworkVehicleType::Ship)
-                .unwrap_or(&0),
-        )?;
-
-        buf.write_u16::<LittleEndian>(
-            *self
-                .num_stations
-                .get(&NetworkVehicleType::Train)
-                .unwrap_or(&0),
-        )?;
-        buf.write_u16::<LittleEndian>(
-            *self
-                .num_stations
-                .get(&NetworkVehicleType::Lorry)
-                .unwrap_or(&0),
-        )?;
-        buf.write_u16::<LittleEndian>(
-            *self
-                .num_stations
-                .get(&NetworkVehicleType::Bus)
-                .unwrap_or(&0),
-        )?;
-        buf.write_u16::<LittleEndian>(
-            *self
-                .num_stations
-                .get(&NetworkVehicleType::Plane)
-                .unwrap_or(&0),
-        )?;
-        buf.write_u16::<LittleEndian>(
-            *self
-                .num_stations
-                .get(&NetworkVehicleType::Ship)
-                .unwrap_or(&0),
-        )?;
-
-        buf.write_u8(if self.is_ai { 1 } else { 0 })?;
+        self.num_vehicles.write_pkt(buf)?;
+        self.num_stations.write_pkt(buf)?;
+
+        if company_info_version >= 6 {
+            buf.write_u8(if self.is_ai { 1 } else { 0 })?;
+        }
+        if company_info_version <= 4 {
+            write_client_list(&self.clients, buf)?;
+        }
 
         Ok(())
     }
 }
 
-named!(pub parse_company_info<&[u8], CompanyInfo>,
+named!(company_info_common<&[u8], CompanyInfo, Error>,
     do_parse!(
         index: le_u8 >>
         name: read_cstring >>
         inaugurated_year: le_u32 >>
-        company_value: le_u64 >>
-        money: le_u64 >>
-        income: le_u64 >>
-        performance_history: le_u16 >>
+        company_value: map!(le_i64, Money) >>
+        money: map!(le_i64, Money) >>
+        income: map!(le_i64, Money) >>
+        performance_history: map!(le_u16, PerformanceRating) >>
         has_password: map!(le_u8, |v| v > 0) >>
 
-        num_vehicles_train: le_u16 >>
-        num_vehicles_lorry: le_u16 >>
-        num_vehicles_bus: le_u16 >>
-        num_vehicles_plane: le_u16 >>
-        num_vehicles_ship: le_u16 >>
+        num_vehicles: parse_vehicle_type_counts >>
+        num_stations: parse_vehicle_type_counts >>
 
-        num_stations_train: le_u16 >>
-        num_stations_lorry: le_u16 >>
-        num_stations_bus: le_u16 >>
-        num_stations_plane: le_u16 >>
-        num_stations_ship: le_u16 >>
-
-        is_ai: map!(le_u8, |v| v > 0) >>
         (CompanyInfo {
             index,
             name,
@@ -142,50 +275,275 @@ named!(pub parse_company_info<&[u8], CompanyInfo>,
             income,
             performance_history,
             has_password,
-            num_vehicles: hashmap! {
-                NetworkVehicleType::Train => num_vehicles_train,
-                NetworkVehicleType::Lorry => num_vehicles_lorry,
-                NetworkVehicleType::Bus => num_vehicles_bus,
-                NetworkVehicleType::Plane => num_vehicles_plane,
-                NetworkVehicleType::Ship => num_vehicles_ship,
-            },
-            num_stations: hashmap! {
-                NetworkVehicleType::Train => num_stations_train,
-                NetworkVehicleType::Lorry => num_stations_lorry,
-                NetworkVehicleType::Bus => num_stations_bus,
-                NetworkVehicleType::Plane => num_stations_plane,
-                NetworkVehicleType::Ship => num_stations_ship,
-            },
-            is_ai,
+            num_vehicles,
+            num_stations,
+            is_ai: false,
+            clients: vec![],
         })
     )
 );
 
+/// Parse a company in the layout of the given company info version
+pub fn parse_company_info(buf: &[u8], company_info_version: u8) -> ParseResult<'_, CompanyInfo> {
+    let (buf, mut company) = company_info_common(buf)?;
+    match company_info_version {
+        0..=4 => {
+            let (buf, clients) = parse_client_list(buf)?;
+            company.clients = clients;
+
+            Ok((buf, company))
+        }
+        5 => Ok((buf, company)),
+        _ => {
+            let (buf, is_ai) = le_u8(buf)?;
+            company.is_ai = is_ai > 0;
+
+            Ok((buf, company))
+        }
+    }
+}
+
+fn check_company_info_version(company_info_version: u8) -> Result<(), Error> {
+    if !(MIN_COMPANY_INFO_VERSION..=NETWORK_COMPANY_INFO_VERSION).contains(&company_info_version) {
+        return Err(Error::UnsupportedType {
+            kind: "company info version",
+            value: company_info_version,
+        });
+    }
+
+    Ok(())
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct ServerDetailInfo {
     pub company_info_version: u8,
     pub companies: Vec<CompanyInfo>,
+    /// Clients not playing as any company, only sent up to company info version 4
+    pub spectators: Vec<ClientInfo>,
 }
 
 impl ServerDetailInfo {
+    /// Shorten all company and client names to the lengths OpenTTD accepts
+    pub fn truncate_to_limits(&mut self) {
+        for company in self.companies.iter_mut() {
+            company.truncate_to_limits();
+        }
+        for client in self.spectators.iter_mut() {
+            client.truncate_to_limits();
+        }
+    }
+
     pub fn write_pkt(&self, buf: &mut Vec<u8>) -> std::io::Result<()> {
+        if self.companies.len() > MAX_COMPANIES {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("company maximum number is {}", MAX_COMPANIES),
+            ));
+        }
+
+        check_company_info_version(self.company_info_version)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+
         buf.write_u8(self.company_info_version)?;
         buf.write_u8(self.companies.len() as u8)?;
         for company in self.companies.iter() {
-            company.write_pkt(buf)?;
+            company.write_pkt(self.company_info_version, buf)?;
+        }
+        if self.company_info_version <= 4 {
+            write_client_list(&self.spectators, buf)?;
         }
 
         Ok(())
     }
 }
 
-named!(pub parse_server_detail_info<&[u8], ServerDetailInfo>,
-    do_parse!(
-        company_info_version: le_u8 >>
-        company_count: le_u8 >>
-        companies: count!(parse_company_info, company_count as usize) >>
-        (ServerDetailInfo {
-            company_info_version, companies
-        })
-    )
-);
+pub fn parse_server_detail_info(buf: &[u8]) -> ParseResult<'_, ServerDetailInfo> {
+    let (buf, company_info_version) = le_u8(buf)?;
+    check_company_info_version(company_info_version).map_err(nom::Err::Failure)?;
+
+    let (buf, company_count) = le_u8(buf)?;
+    let (buf, companies) = count!(
+        buf,
+        call!(parse_company_info, company_info_version),
+        company_count as usize
+    )?;
+    let (buf, spectators) = if company_info_version <= 4 {
+        parse_client_list(buf)?
+    } else {
+        (buf, vec![])
+    };
+
+    Ok((
+        buf,
+        ServerDetailInfo {
+            company_info_version,
+            companies,
+            spectators,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use hex_literal::hex;
+
+    fn company() -> CompanyInfo {
+        CompanyInfo {
+            index: 0,
+            name: CString::new("Acme").unwrap(),
+            inaugurated_year: 1950,
+            company_value: Money(100_000),
+            money: Money(50_000),
+            income: Money(-1_000),
+            performance_history: PerformanceRating(256),
+            has_password: false,
+            num_vehicles: VehicleTypeCounts::from([2, 0, 1, 0, 0]),
+            num_stations: VehicleTypeCounts::from([1, 0, 1, 0, 0]),
+            is_ai: true,
+            clients: vec![],
+        }
+    }
+
+    fn fixtures() -> (Vec<u8>, ServerDetailInfo) {
+        let b = hex!(
+            "
+            0601
+            00 41636D6500 9E070000
+            A086010000000000 50C3000000000000 18FCFFFFFFFFFFFF
+            0001 00
+            02000000010000000000
+            01000000010000000000
+            01
+        "
+        )
+        .to_vec();
+
+        let data = ServerDetailInfo {
+            company_info_version: 6,
+            companies: vec![company()],
+            spectators: vec![],
+        };
+
+        (b, data)
+    }
+
+    fn fixtures_v5() -> (Vec<u8>, ServerDetailInfo) {
+        let b = hex!(
+            "
+            0501
+            00 41636D6500 9E070000
+            A086010000000000 50C3000000000000 18FCFFFFFFFFFFFF
+            0001 00
+            02000000010000000000
+            01000000010000000000
+        "
+        )
+        .to_vec();
+
+        let data = ServerDetailInfo {
+            company_info_version: 5,
+            companies: vec![CompanyInfo {
+                is_ai: false,
+                ..company()
+            }],
+            spectators: vec![],
+        };
+
+        (b, data)
+    }
+
+    fn fixtures_v4() -> (Vec<u8>, ServerDetailInfo) {
+        let b = hex!(
+            "
+            0401
+            00 41636D6500 9E070000
+            A086010000000000 50C3000000000000 18FCFFFFFFFFFFFF
+            0001 00
+            02000000010000000000
+            01000000010000000000
+            01 416C69636500 616263646500 D8AE0A00
+            00
+            01 426F6200 00 D9AE0A00
+            00
+        "
+        )
+        .to_vec();
+
+        let data = ServerDetailInfo {
+            company_info_version: 4,
+            companies: vec![CompanyInfo {
+                is_ai: false,
+                clients: vec![ClientInfo {
+                    name: CString::new("Alice").unwrap(),
+                    unique_id: CString::new("abcde").unwrap(),
+                    join_date: OpenTTDDate(700_120),
+                }],
+                ..company()
+            }],
+            spectators: vec![ClientInfo {
+                name: CString::new("Bob").unwrap(),
+                unique_id: CString::default(),
+                join_date: OpenTTDDate(700_121),
+            }],
+        };
+
+        (b, data)
+    }
+
+    #[test]
+    fn test_parse_server_detail_info() {
+        for (input, expectation) in [fixtures(), fixtures_v5(), fixtures_v4()] {
+            let result = parse_server_detail_info(&input).unwrap();
+
+            assert_eq!(expectation, result.1);
+            assert!(result.0.is_empty());
+        }
+
+        assert_eq!(
+            parse_server_detail_info(&[3, 0]),
+            Err(nom::Err::Failure(Error::UnsupportedType {
+                kind: "company info version",
+                value: 3
+            }))
+        );
+    }
+
+    #[test]
+    fn test_vehicle_type_counts() {
+        let mut counts = VehicleTypeCounts::default();
+        counts[NetworkVehicleType::Bus] = 3;
+        counts[NetworkVehicleType::Ship] += 2;
+
+        assert_eq!(counts, VehicleTypeCounts::from([0, 0, 3, 0, 2]));
+        assert_eq!(counts.total(), 5);
+        assert_eq!(counts.iter().nth(2), Some((NetworkVehicleType::Bus, 3)));
+
+        assert_eq!(NetworkVehicleType::ALL.len(), NETWORK_VEH_END);
+        for (i, vehicle_type) in NetworkVehicleType::ALL.iter().enumerate() {
+            assert_eq!(vehicle_type.index(), i);
+        }
+    }
+
+    #[test]
+    fn test_performance_rating() {
+        assert_eq!(PerformanceRating(0).title(), "Businessman");
+        assert_eq!(PerformanceRating(450).title(), "Industrialist");
+        assert_eq!(
+            PerformanceRating(SCORE_MAX).title(),
+            "Tycoon of the Century"
+        );
+        assert_eq!(PerformanceRating(250).fraction(), 0.25);
+    }
+
+    #[test]
+    fn test_write_server_detail_info() {
+        for (expectation, input) in [fixtures(), fixtures_v5(), fixtures_v4()] {
+            let mut result = Vec::new();
+            input.write_pkt(&mut result).unwrap();
+
+            assert_eq!(expectation, result);
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use crate::error::*;
+use crate::limits::*;
+use crate::server_response::{newgrf_entry, NewGRFHash};
+use crate::util::*;
+
+use byteorder::{LittleEndian, WriteBytesExt};
+use nom::{self, number::complete::*, *};
+use std::ffi::CString;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NewGRFInfo {
+    pub id: u32,
+    pub hash: NewGRFHash,
+    pub name: CString,
+}
+
+impl ByteWriter for NewGRFInfo {
+    fn write_pkt(&self, buf: &mut Vec<u8>) -> std::io::Result<()> {
+        buf.write_u32::<LittleEndian>(self.id)?;
+        buf.extend_from_slice(&self.hash.0);
+        write_cstring(buf, &self.name, NETWORK_GRF_NAME_LENGTH, "NewGRF name")?;
+
+        Ok(())
+    }
+}
+
+named!(parse_newgrf_info<&[u8], NewGRFInfo, Error>,
+    do_parse!(
+        entry: newgrf_entry >>
+        name: read_cstring >>
+        (NewGRFInfo {
+            id: entry.0,
+            hash: entry.1,
+            name,
+        })
+    )
+);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ServerNewGRFsData {
+    pub newgrfs: Vec<NewGRFInfo>,
+}
+
+impl ServerNewGRFsData {
+    /// Shorten all NewGRF names to the length OpenTTD accepts
+    pub fn truncate_to_limits(&mut self) {
+        for info in self.newgrfs.iter_mut() {
+            truncate_cstring(&mut info.name, NETWORK_GRF_NAME_LENGTH);
+        }
+    }
+
+    /// Look up the name of a NewGRF by its ID and MD5 hash
+    pub fn name_of(&self, id: u32, hash: &NewGRFHash) -> Option<&CString> {
+        self.newgrfs
+            .iter()
+            .find(|info| info.id == id && info.hash == *hash)
+            .map(|info| &info.name)
+    }
+}
+
+impl ByteWriter for ServerNewGRFsData {
+    fn write_pkt(&self, buf: &mut Vec<u8>) -> std::io::Result<()> {
+        write_newgrf_count(self.newgrfs.len(), buf)?;
+        for info in self.newgrfs.iter() {
+            info.write_pkt(buf)?;
+        }
+
+        Ok(())
+    }
+}
+
+named!(pub parse_server_newgrfs<&[u8], ServerNewGRFsData, Error>,
+    do_parse!(
+        newgrf_num: le_u8 >>
+        newgrfs: count!(parse_newgrf_info, newgrf_num as usize) >>
+        (ServerNewGRFsData { newgrfs })
+    )
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use hex_literal::hex;
+
+    fn fixtures() -> (Vec<u8>, ServerNewGRFsData) {
+        let b = hex!(
+            "
+            01444E0700
+            48B3F9E4FD0DF2A72B5F44D3C8A2F4A0
+            4F70656E474658202D2062617365206772617068696373
+            00
+        "
+        )
+        .to_vec();
+
+        let data = ServerNewGRFsData {
+            newgrfs: vec![NewGRFInfo {
+                id: 0x00074e44,
+                hash: NewGRFHash(hex!("48b3f9e4fd0df2a72b5f44d3c8a2f4a0")),
+                name: CString::new("OpenGFX - base graphics").unwrap(),
+            }],
+        };
+
+        (b, data)
+    }
+
+    #[test]
+    fn test_parse_server_newgrfs() {
+        let (input, expectation) = fixtures();
+
+        let result = parse_server_newgrfs(&input).unwrap();
+
+        assert_eq!(expectation, result.1);
+    }
+
+    #[test]
+    fn test_write_server_newgrfs() {
+        let (expectation, input) = fixtures();
+
+        let mut result = Vec::new();
+        input.write_pkt(&mut result).unwrap();
+
+        assert_eq!(expectation, result);
+    }
+}
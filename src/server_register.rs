@@ -1,8 +1,9 @@
+use crate::error::*;
+use crate::limits::*;
 use crate::util::*;
 
 use byteorder::{LittleEndian, WriteBytesExt};
 use nom::{self, number::complete::*, *};
-use std;
 use std::ffi::CString;
 
 #[derive(Clone, Debug, PartialEq)]
@@ -14,8 +15,18 @@ pub struct ServerRegistrationData {
 }
 
 impl ServerRegistrationData {
+    /// Shorten the welcome message to the length OpenTTD accepts
+    pub fn truncate_to_limits(&mut self) {
+        truncate_cstring(&mut self.welcome_message, NETWORK_NAME_LENGTH);
+    }
+
     pub fn write_pkt(&self, buf: &mut Vec<u8>) -> std::io::Result<()> {
-        buf.append(&mut self.welcome_message.clone().into_bytes_with_nul());
+        write_cstring(
+            buf,
+            &self.welcome_message,
+            NETWORK_NAME_LENGTH,
+            "welcome message",
+        )?;
         buf.write_u8(self.server_version)?;
         buf.write_u16::<LittleEndian>(self.port)?;
         buf.write_u64::<LittleEndian>(self.session_key)?;
@@ -24,7 +35,7 @@ impl ServerRegistrationData {
     }
 }
 
-named!(pub parse_server_register<&[u8], ServerRegistrationData>,
+named!(pub parse_server_register<&[u8], ServerRegistrationData, Error>,
     do_parse!(
         welcome_message: read_cstring >>
         server_version: le_u8 >>
@@ -1,14 +1,16 @@
+use crate::date::*;
+use crate::error::*;
+use crate::limits::*;
+use crate::network_language::*;
 use crate::util::*;
 
 use byteorder::{LittleEndian, WriteBytesExt};
-use chrono::prelude::*;
 use nom::{self, number::complete::*, *};
-use std;
 use std::collections::{BTreeMap, HashMap};
 use std::ffi::CString;
 use std::fmt;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct V2Data {
     pub max_companies: u8,
     pub current_companies: u8,
@@ -25,7 +27,7 @@ impl ByteWriter for V2Data {
     }
 }
 
-named!(parse_v2_data<&[u8], V2Data>,
+named!(parse_v2_data<&[u8], V2Data, Error>,
     do_parse!(
         max_companies: be_u8 >>
         current_companies: be_u8 >>
@@ -34,29 +36,58 @@ named!(parse_v2_data<&[u8], V2Data>,
     )
 );
 
+/// Game and start date. Sent in every game info version, as days since 1920 before version 3.
 #[derive(Clone, Debug, PartialEq)]
 pub struct V3Data {
-    pub game_date: DateTime<Utc>,
-    pub start_date: DateTime<Utc>,
+    pub game_date: OpenTTDDate,
+    pub start_date: OpenTTDDate,
+}
+
+impl Default for V3Data {
+    /// 1 January 1920, the earliest date versions 1 and 2 can send
+    fn default() -> Self {
+        Self {
+            game_date: OpenTTDDate(DAYS_TILL_ORIGINAL_BASE_YEAR),
+            start_date: OpenTTDDate(DAYS_TILL_ORIGINAL_BASE_YEAR),
+        }
+    }
 }
 
 impl ByteWriter for V3Data {
     fn write_pkt(&self, buf: &mut Vec<u8>) -> std::io::Result<()> {
-        buf.write_u32::<LittleEndian>(self.game_date.timestamp() as u32)?;
-        buf.write_u32::<LittleEndian>(self.start_date.timestamp() as u32)?;
+        self.game_date.write_pkt(buf)?;
+        self.start_date.write_pkt(buf)?;
 
         Ok(())
     }
 }
 
-named!(datetime<&[u8], DateTime<Utc>>,
-    map!(le_u32, datetime_from_ts)
+named!(parse_v3_data<&[u8], V3Data, Error>,
+    do_parse!(
+        game_date: parse_date >>
+        start_date: parse_date >>
+        (V3Data { game_date, start_date })
+    )
 );
 
-named!(parse_v3_data<&[u8], V3Data>,
+impl V3Data {
+    /// Whether both dates can be sent as days since 1920, as game info versions 1 and 2 do
+    pub fn fits_original_dates(&self) -> bool {
+        self.game_date.to_original_days().is_some() && self.start_date.to_original_days().is_some()
+    }
+
+    fn write_original(&self, buf: &mut Vec<u8>) -> std::io::Result<()> {
+        write_original_date(self.game_date, buf)?;
+        write_original_date(self.start_date, buf)?;
+
+        Ok(())
+    }
+}
+
+named!(parse_original_v3_data<&[u8], V3Data, Error>,
     do_parse!(
-        game_date: datetime >>
-        start_date: datetime >>
+        game_date: parse_original_date >>
+        start_date: parse_original_date >>
         (V3Data { game_date, start_date })
     )
 );
@@ -73,21 +104,14 @@ impl fmt::Display for NewGRFHash {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct V4Data {
     pub active_newgrf: HashMap<u32, NewGRFHash>,
 }
 
 impl ByteWriter for V4Data {
     fn write_pkt(&self, buf: &mut Vec<u8>) -> std::io::Result<()> {
-        if self.active_newgrf.len() > 255 {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                "NewGRF maximum number is 255",
-            ));
-        }
-
-        buf.push(self.active_newgrf.len() as u8);
+        write_newgrf_count(self.active_newgrf.len(), buf)?;
         for (id, hash) in self
             .active_newgrf
             .clone()
@@ -103,7 +127,7 @@ impl ByteWriter for V4Data {
     }
 }
 
-named!(newgrf_md5<&[u8], NewGRFHash>,
+named!(pub(crate) newgrf_md5<&[u8], NewGRFHash, Error>,
     map!(take!(16), |v| {
         let mut out = [0; 16];
         out.copy_from_slice(v);
@@ -111,7 +135,7 @@ named!(newgrf_md5<&[u8], NewGRFHash>,
     })
 );
 
-named!(newgrf_entry<&[u8], (u32, NewGRFHash)>,
+named!(pub(crate) newgrf_entry<&[u8], (u32, NewGRFHash), Error>,
     do_parse!(
         id:  le_u32 >>
         md5: newgrf_md5 >>
@@ -119,7 +143,7 @@ named!(newgrf_entry<&[u8], (u32, NewGRFHash)>,
     )
 );
 
-named!(parse_v4_data<&[u8], V4Data>,
+named!(parse_v4_data<&[u8], V4Data, Error>,
     do_parse!(
         active_newgrf_num: le_u8 >>
         newgrf_data: count!(newgrf_entry, active_newgrf_num as usize) >>
@@ -129,31 +153,357 @@ named!(parse_v4_data<&[u8], V4Data>,
     )
 );
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct V5Data {
+    /// Version of the running GameScript, -1 if there is none
+    pub gamescript_version: i32,
+    pub gamescript_name: CString,
+}
+
+impl Default for V5Data {
+    fn default() -> Self {
+        Self {
+            gamescript_version: -1,
+            gamescript_name: CString::default(),
+        }
+    }
+}
+
+impl ByteWriter for V5Data {
+    fn write_pkt(&self, buf: &mut Vec<u8>) -> std::io::Result<()> {
+        buf.write_i32::<LittleEndian>(self.gamescript_version)?;
+        write_cstring(
+            buf,
+            &self.gamescript_name,
+            NETWORK_NAME_LENGTH,
+            "GameScript name",
+        )?;
+
+        Ok(())
+    }
+}
+
+named!(parse_v5_data<&[u8], V5Data, Error>,
+    do_parse!(
+        gamescript_version: le_i32 >>
+        gamescript_name: read_cstring >>
+        (V5Data { gamescript_version, gamescript_name })
+    )
+);
+
+/// How the NewGRF list is encoded since game info version 6
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum NewGRFSerialisation {
+    /// GRF ID and MD5 checksum, same as in version 4
+    #[default]
+    GrfIdMd5,
+    /// GRF ID, MD5 checksum and name of every NewGRF, keyed by GRF ID
+    GrfIdMd5Name(HashMap<u32, CString>),
+    /// Indices into a lookup table shared out of band. `V4Data` is left empty when parsing and
+    /// must be empty when writing, as this serialisation has no room for GRF IDs and checksums.
+    LookupId(Vec<u32>),
+}
+
+impl<'a> From<&'a NewGRFSerialisation> for u8 {
+    fn from(v: &'a NewGRFSerialisation) -> u8 {
+        match *v {
+            NewGRFSerialisation::GrfIdMd5 => 0,
+            NewGRFSerialisation::GrfIdMd5Name(_) => 1,
+            NewGRFSerialisation::LookupId(_) => 2,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct V6Data {
+    pub newgrf_serialisation: NewGRFSerialisation,
+}
+
+impl V6Data {
+    /// Write the NewGRF list of `v4data` in the serialisation chosen by self
+    fn write_newgrfs(&self, v4data: &V4Data, buf: &mut Vec<u8>) -> std::io::Result<()> {
+        match self.newgrf_serialisation {
+            NewGRFSerialisation::GrfIdMd5 => v4data.write_pkt(buf),
+            NewGRFSerialisation::GrfIdMd5Name(ref names) => {
+                write_newgrf_count(v4data.active_newgrf.len(), buf)?;
+                for (id, hash) in v4data.active_newgrf.iter().collect::<BTreeMap<_, _>>() {
+                    buf.write_u32::<LittleEndian>(*id)?;
+                    buf.extend_from_slice(&hash.0);
+                    match names.get(id) {
+                        Some(name) => {
+                            write_cstring(buf, name, NETWORK_GRF_NAME_LENGTH, "NewGRF name")?
+                        }
+                        None => buf.push(0),
+                    }
+                }
+
+                Ok(())
+            }
+            NewGRFSerialisation::LookupId(ref ids) => {
+                if !v4data.active_newgrf.is_empty() {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        "NewGRF lookup IDs cannot carry GRF IDs and checksums",
+                    ));
+                }
+                write_newgrf_count(ids.len(), buf)?;
+                for id in ids.iter() {
+                    buf.write_u32::<LittleEndian>(*id)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+named!(newgrf_entry_with_name<&[u8], (u32, NewGRFHash, CString), Error>,
+    do_parse!(
+        entry: newgrf_entry >>
+        name: read_cstring >>
+        (entry.0, entry.1, name)
+    )
+);
+
+/// Parse the NewGRF list in the serialisation announced by a version 6+ header
+fn parse_v6_newgrfs(buf: &[u8], serialisation: u8) -> ParseResult<'_, (V4Data, V6Data)> {
+    let (buf, newgrf_num) = le_u8(buf)?;
+    let newgrf_num = newgrf_num as usize;
+    let (buf, v4, newgrf_serialisation) = match serialisation {
+        0 => {
+            let (buf, newgrf_data) = count!(buf, newgrf_entry, newgrf_num)?;
+            let v4 = V4Data {
+                active_newgrf: newgrf_data.into_iter().collect(),
+            };
+            (buf, v4, NewGRFSerialisation::GrfIdMd5)
+        }
+        1 => {
+            let (buf, newgrf_data) = count!(buf, newgrf_entry_with_name, newgrf_num)?;
+            let mut active_newgrf = HashMap::new();
+            let mut names = HashMap::new();
+            for (id, hash, name) in newgrf_data {
+                active_newgrf.insert(id, hash);
+                names.insert(id, name);
+            }
+            let v4 = V4Data { active_newgrf };
+            (buf, v4, NewGRFSerialisation::GrfIdMd5Name(names))
+        }
+        2 => {
+            let (buf, ids) = count!(buf, le_u32, newgrf_num)?;
+            let v4 = V4Data {
+                active_newgrf: HashMap::new(),
+            };
+            (buf, v4, NewGRFSerialisation::LookupId(ids))
+        }
+        value => {
+            return Err(nom::Err::Failure(Error::UnknownType {
+                kind: "NewGRF serialisation",
+                value,
+            }))
+        }
+    };
+
+    Ok((
+        buf,
+        (
+            v4,
+            V6Data {
+                newgrf_serialisation,
+            },
+        ),
+    ))
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct V7Data {
+    /// Number of ticks the game has been running
+    pub ticks_playing: u64,
+}
+
+impl ByteWriter for V7Data {
+    fn write_pkt(&self, buf: &mut Vec<u8>) -> std::io::Result<()> {
+        buf.write_u64::<LittleEndian>(self.ticks_playing)?;
+
+        Ok(())
+    }
+}
+
+named!(parse_v7_data<&[u8], V7Data, Error>,
+    do_parse!(
+        ticks_playing: le_u64 >>
+        (V7Data { ticks_playing })
+    )
+);
+
+/// Newest game info version this crate can encode
+pub const NETWORK_GAME_INFO_VERSION: u8 = 7;
+
+/// Fail with `Error::UnknownType` for version 0 and `Error::UnsupportedType` for versions newer
+/// than `NETWORK_GAME_INFO_VERSION`
+pub(crate) fn check_game_info_version(version: u8) -> Result<(), Error> {
+    match version {
+        0 => Err(Error::UnknownType {
+            kind: "game info version",
+            value: version,
+        }),
+        v if v > NETWORK_GAME_INFO_VERSION => Err(Error::UnsupportedType {
+            kind: "game info version",
+            value: version,
+        }),
+        _ => Ok(()),
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum ProtocolVer {
-    V1,
-    V2(V2Data),
+    /// Dates are sent after the client counts instead of in the version block
+    V1(V3Data),
+    /// Dates are sent after the client counts instead of in the version block
+    V2(V2Data, V3Data),
     V3(V2Data, V3Data),
     V4(V2Data, V3Data, V4Data),
+    V5(V2Data, V3Data, V4Data, V5Data),
+    V6(V2Data, V3Data, V4Data, V5Data, V6Data),
+    V7(V2Data, V3Data, V4Data, V5Data, V6Data, V7Data),
 }
 
 impl<'a> From<&'a ProtocolVer> for u8 {
     fn from(v: &'a ProtocolVer) -> u8 {
         match *v {
-            ProtocolVer::V1 => 1,
-            ProtocolVer::V2(_) => 2,
+            ProtocolVer::V1(_) => 1,
+            ProtocolVer::V2(_, _) => 2,
             ProtocolVer::V3(_, _) => 3,
             ProtocolVer::V4(_, _, _) => 4,
+            ProtocolVer::V5(_, _, _, _) => 5,
+            ProtocolVer::V6(_, _, _, _, _) => 6,
+            ProtocolVer::V7(_, _, _, _, _, _) => 7,
+        }
+    }
+}
+
+impl ProtocolVer {
+    fn truncate_to_limits(&mut self) {
+        match *self {
+            ProtocolVer::V5(_, _, _, ref mut v5data) => {
+                truncate_cstring(&mut v5data.gamescript_name, NETWORK_NAME_LENGTH);
+            }
+            ProtocolVer::V6(_, _, _, ref mut v5data, ref mut v6data)
+            | ProtocolVer::V7(_, _, _, ref mut v5data, ref mut v6data, _) => {
+                truncate_cstring(&mut v5data.gamescript_name, NETWORK_NAME_LENGTH);
+                if let NewGRFSerialisation::GrfIdMd5Name(ref mut names) =
+                    v6data.newgrf_serialisation
+                {
+                    for name in names.values_mut() {
+                        truncate_cstring(name, NETWORK_GRF_NAME_LENGTH);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl ProtocolVer {
+    /// Game and start date, which every version carries
+    pub fn dates(&self) -> &V3Data {
+        match *self {
+            ProtocolVer::V1(ref v3data)
+            | ProtocolVer::V2(_, ref v3data)
+            | ProtocolVer::V3(_, ref v3data)
+            | ProtocolVer::V4(_, ref v3data, _)
+            | ProtocolVer::V5(_, ref v3data, _, _)
+            | ProtocolVer::V6(_, ref v3data, _, _, _)
+            | ProtocolVer::V7(_, ref v3data, _, _, _, _) => v3data,
+        }
+    }
+
+    fn dates_mut(&mut self) -> &mut V3Data {
+        match *self {
+            ProtocolVer::V1(ref mut v3data)
+            | ProtocolVer::V2(_, ref mut v3data)
+            | ProtocolVer::V3(_, ref mut v3data)
+            | ProtocolVer::V4(_, ref mut v3data, _)
+            | ProtocolVer::V5(_, ref mut v3data, _, _)
+            | ProtocolVer::V6(_, ref mut v3data, _, _, _)
+            | ProtocolVer::V7(_, ref mut v3data, _, _, _, _) => v3data,
+        }
+    }
+}
+
+/// Version specific blocks of a `ProtocolVer`, `None` where the version does not carry them
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ProtocolVerParts {
+    pub v2: Option<V2Data>,
+    pub v3: Option<V3Data>,
+    pub v4: Option<V4Data>,
+    pub v5: Option<V5Data>,
+    pub v6: Option<V6Data>,
+    pub v7: Option<V7Data>,
+}
+
+impl From<ProtocolVer> for ProtocolVerParts {
+    fn from(v: ProtocolVer) -> Self {
+        let (v2, v3, v4, v5, v6, v7) = match v {
+            ProtocolVer::V1(v3) => (None, Some(v3), None, None, None, None),
+            ProtocolVer::V2(v2, v3) => (Some(v2), Some(v3), None, None, None, None),
+            ProtocolVer::V3(v2, v3) => (Some(v2), Some(v3), None, None, None, None),
+            ProtocolVer::V4(v2, v3, v4) => (Some(v2), Some(v3), Some(v4), None, None, None),
+            ProtocolVer::V5(v2, v3, v4, v5) => (Some(v2), Some(v3), Some(v4), Some(v5), None, None),
+            ProtocolVer::V6(v2, v3, v4, v5, v6) => {
+                (Some(v2), Some(v3), Some(v4), Some(v5), Some(v6), None)
+            }
+            ProtocolVer::V7(v2, v3, v4, v5, v6, v7) => {
+                (Some(v2), Some(v3), Some(v4), Some(v5), Some(v6), Some(v7))
+            }
+        };
+
+        ProtocolVerParts {
+            v2,
+            v3,
+            v4,
+            v5,
+            v6,
+            v7,
         }
     }
 }
 
+impl ProtocolVer {
+    /// Split into the version specific blocks
+    pub fn into_parts(self) -> ProtocolVerParts {
+        self.into()
+    }
+
+    /// Assemble the given game info version from its blocks, dropping those it does not carry
+    pub(crate) fn from_parts(
+        version: u8,
+        v2: V2Data,
+        v3: V3Data,
+        v4: V4Data,
+        v5: V5Data,
+        v6: V6Data,
+        v7: V7Data,
+    ) -> Result<ProtocolVer, Error> {
+        check_game_info_version(version)?;
+
+        Ok(match version {
+            1 => ProtocolVer::V1(v3),
+            2 => ProtocolVer::V2(v2, v3),
+            3 => ProtocolVer::V3(v2, v3),
+            4 => ProtocolVer::V4(v2, v3, v4),
+            5 => ProtocolVer::V5(v2, v3, v4, v5),
+            6 => ProtocolVer::V6(v2, v3, v4, v5, v6),
+            _ => ProtocolVer::V7(v2, v3, v4, v5, v6, v7),
+        })
+    }
+}
+
 impl ByteWriter for ProtocolVer {
     fn write_pkt(&self, buf: &mut Vec<u8>) -> std::io::Result<()> {
-        buf.push((&*self).into());
+        buf.push(self.into());
         match *self {
-            ProtocolVer::V1 => {}
-            ProtocolVer::V2(ref v2data) => {
+            ProtocolVer::V1(_) => {}
+            ProtocolVer::V2(ref v2data, _) => {
                 v2data.write_pkt(buf)?;
             }
             ProtocolVer::V3(ref v2data, ref v3data) => {
@@ -165,18 +515,48 @@ impl ByteWriter for ProtocolVer {
                 v3data.write_pkt(buf)?;
                 v2data.write_pkt(buf)?;
             }
+            ProtocolVer::V5(ref v2data, ref v3data, ref v4data, ref v5data) => {
+                v5data.write_pkt(buf)?;
+                v4data.write_pkt(buf)?;
+                v3data.write_pkt(buf)?;
+                v2data.write_pkt(buf)?;
+            }
+            ProtocolVer::V6(ref v2data, ref v3data, ref v4data, ref v5data, ref v6data) => {
+                buf.push((&v6data.newgrf_serialisation).into());
+                v5data.write_pkt(buf)?;
+                v6data.write_newgrfs(v4data, buf)?;
+                v3data.write_pkt(buf)?;
+                v2data.write_pkt(buf)?;
+            }
+            ProtocolVer::V7(
+                ref v2data,
+                ref v3data,
+                ref v4data,
+                ref v5data,
+                ref v6data,
+                ref v7data,
+            ) => {
+                v7data.write_pkt(buf)?;
+                buf.push((&v6data.newgrf_serialisation).into());
+                v5data.write_pkt(buf)?;
+                v6data.write_newgrfs(v4data, buf)?;
+                v3data.write_pkt(buf)?;
+                v2data.write_pkt(buf)?;
+            }
         }
         Ok(())
     }
 }
 
-fn protocol_ver(buf: &[u8]) -> nom::IResult<&[u8], ProtocolVer> {
+fn protocol_ver(buf: &[u8]) -> ParseResult<'_, ProtocolVer> {
     let (buf, protocol_num) = be_u8(buf)?;
+    check_game_info_version(protocol_num).map_err(nom::Err::Failure)?;
     match protocol_num {
-        1 => Ok((buf, ProtocolVer::V1)),
+        // The dates follow later in the packet, see `ProtocolVer::dates_mut`
+        1 => Ok((buf, ProtocolVer::V1(V3Data::default()))),
         2 => {
             let (buf, v2) = parse_v2_data(buf)?;
-            Ok((buf, ProtocolVer::V2(v2)))
+            Ok((buf, ProtocolVer::V2(v2, V3Data::default())))
         }
         3 => {
             let (buf, v3) = parse_v3_data(buf)?;
@@ -189,7 +569,93 @@ fn protocol_ver(buf: &[u8]) -> nom::IResult<&[u8], ProtocolVer> {
             let (buf, v2) = parse_v2_data(buf)?;
             Ok((buf, ProtocolVer::V4(v2, v3, v4)))
         }
-        _ => Err(nom::Err::Failure((buf, nom::error::ErrorKind::OneOf))),
+        5 => {
+            let (buf, v5) = parse_v5_data(buf)?;
+            let (buf, v4) = parse_v4_data(buf)?;
+            let (buf, v3) = parse_v3_data(buf)?;
+            let (buf, v2) = parse_v2_data(buf)?;
+            Ok((buf, ProtocolVer::V5(v2, v3, v4, v5)))
+        }
+        6 => {
+            let (buf, serialisation) = le_u8(buf)?;
+            let (buf, v5) = parse_v5_data(buf)?;
+            let (buf, (v4, v6)) = parse_v6_newgrfs(buf, serialisation)?;
+            let (buf, v3) = parse_v3_data(buf)?;
+            let (buf, v2) = parse_v2_data(buf)?;
+            Ok((buf, ProtocolVer::V6(v2, v3, v4, v5, v6)))
+        }
+        7 => {
+            let (buf, v7) = parse_v7_data(buf)?;
+            let (buf, serialisation) = le_u8(buf)?;
+            let (buf, v5) = parse_v5_data(buf)?;
+            let (buf, (v4, v6)) = parse_v6_newgrfs(buf, serialisation)?;
+            let (buf, v3) = parse_v3_data(buf)?;
+            let (buf, v2) = parse_v2_data(buf)?;
+            Ok((buf, ProtocolVer::V7(v2, v3, v4, v5, v6, v7)))
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Climate of the map
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Landscape {
+    Temperate,
+    SubArctic,
+    SubTropical,
+    Toyland,
+    Unknown(u8),
+}
+
+impl From<u8> for Landscape {
+    fn from(v: u8) -> Self {
+        use self::Landscape::*;
+
+        match v {
+            0 => Temperate,
+            1 => SubArctic,
+            2 => SubTropical,
+            3 => Toyland,
+            other => Unknown(other),
+        }
+    }
+}
+
+impl From<Landscape> for u8 {
+    fn from(v: Landscape) -> Self {
+        use self::Landscape::*;
+
+        match v {
+            Temperate => 0,
+            SubArctic => 1,
+            SubTropical => 2,
+            Toyland => 3,
+            Unknown(other) => other,
+        }
+    }
+}
+
+impl Landscape {
+    /// Human-readable name, as shown by OpenTTD
+    pub fn name(&self) -> &'static str {
+        use self::Landscape::*;
+
+        match *self {
+            Temperate => "Temperate",
+            SubArctic => "Sub-arctic",
+            SubTropical => "Sub-tropical",
+            Toyland => "Toyland",
+            Unknown(_) => "Unknown",
+        }
+    }
+}
+
+impl fmt::Display for Landscape {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            Landscape::Unknown(v) => write!(fmt, "Unknown ({})", v),
+            _ => write!(fmt, "{}", self.name()),
+        }
     }
 }
 
@@ -198,73 +664,120 @@ pub struct ServerResponse {
     pub protocol_ver: ProtocolVer,
     pub server_name: CString,
     pub server_revision: CString,
-    pub server_lang: u8,
+    /// Not sent since game info version 6, `Any` there
+    pub server_lang: NetworkLanguage,
     pub use_password: bool,
     pub clients_max: u8,
     pub clients_on: u8,
     pub spectators_on: u8,
+    /// Not sent since game info version 6, empty there
     pub map_name: CString,
     pub map_width: u16,
     pub map_height: u16,
-    pub map_set: u8,
+    pub map_set: Landscape,
     pub dedicated: bool,
 }
 
+impl ServerResponse {
+    /// Shorten all strings to the lengths OpenTTD accepts, so that writing does not fail on them
+    pub fn truncate_to_limits(&mut self) {
+        self.protocol_ver.truncate_to_limits();
+        truncate_cstring(&mut self.server_name, NETWORK_NAME_LENGTH);
+        truncate_cstring(&mut self.server_revision, NETWORK_REVISION_LENGTH);
+        truncate_cstring(&mut self.map_name, NETWORK_NAME_LENGTH);
+    }
+
+    /// Whether the game info version still sends server language and map name, which were
+    /// dropped in version 6
+    pub fn sends_lang_and_map_name(version: u8) -> bool {
+        version < 6
+    }
+
+    /// Reset server language and map name to their defaults if the response's version does not
+    /// send them. Returns the previous values.
+    pub(crate) fn take_unsent_lang_and_map_name(&mut self) -> Option<(NetworkLanguage, CString)> {
+        if Self::sends_lang_and_map_name((&self.protocol_ver).into()) {
+            return None;
+        }
+
+        Some((
+            std::mem::take(&mut self.server_lang),
+            std::mem::take(&mut self.map_name),
+        ))
+    }
+}
+
 impl ByteWriter for ServerResponse {
     fn write_pkt(&self, buf: &mut Vec<u8>) -> std::io::Result<()> {
         self.protocol_ver.write_pkt(buf)?;
-        buf.append(&mut self.server_name.clone().into_bytes_with_nul());
-        buf.append(&mut self.server_revision.clone().into_bytes_with_nul());
-        buf.push(self.server_lang);
+        write_cstring(buf, &self.server_name, NETWORK_NAME_LENGTH, "server name")?;
+        write_cstring(
+            buf,
+            &self.server_revision,
+            NETWORK_REVISION_LENGTH,
+            "server revision",
+        )?;
+        let sends_lang_and_map_name = Self::sends_lang_and_map_name((&self.protocol_ver).into());
+        if sends_lang_and_map_name {
+            buf.push(self.server_lang.into());
+        }
         buf.push(if self.use_password { 1 } else { 0 });
         buf.push(self.clients_max);
         buf.push(self.clients_on);
         buf.push(self.spectators_on);
 
         if u8::from(&self.protocol_ver) < 3 {
-            buf.append(&mut vec![0; 4]);
+            self.protocol_ver.dates().write_original(buf)?;
         }
 
-        buf.append(&mut self.map_name.clone().into_bytes_with_nul());
+        if sends_lang_and_map_name {
+            write_cstring(buf, &self.map_name, NETWORK_NAME_LENGTH, "map name")?;
+        }
         buf.write_u16::<LittleEndian>(self.map_width)?;
         buf.write_u16::<LittleEndian>(self.map_height)?;
-        buf.push(self.map_set);
+        buf.push(self.map_set.into());
         buf.push(if self.dedicated { 1 } else { 0 });
 
         Ok(())
     }
 }
 
-named!(pub parse_server_response<&[u8], ServerResponse>,
+named!(pub parse_server_response<&[u8], ServerResponse, Error>,
     do_parse!(
         protocol_ver: protocol_ver >>
         server_name: read_cstring >>
         server_revision: read_cstring >>
 
-        server_lang: le_u8 >>
+        server_lang: cond!(ServerResponse::sends_lang_and_map_name((&protocol_ver).into()), map!(le_u8, NetworkLanguage::from)) >>
         use_password: map!(le_u8, |v| v > 0) >>
         clients_max: le_u8 >>
         clients_on: le_u8 >>
         spectators_on: le_u8 >>
 
-        cond!(u8::from(&protocol_ver) < 3, take!(4)) >>
+        original_dates: cond!(u8::from(&protocol_ver) < 3, parse_original_v3_data) >>
 
-        map_name: read_cstring >>
+        map_name: cond!(ServerResponse::sends_lang_and_map_name((&protocol_ver).into()), read_cstring) >>
         map_width: le_u16 >>
         map_height: le_u16 >>
-        map_set: le_u8 >>
+        map_set: map!(le_u8, Landscape::from) >>
         dedicated: map!(le_u8, |v| v > 0) >>
 
         (ServerResponse {
-            protocol_ver,
+            protocol_ver: {
+                let mut protocol_ver = protocol_ver;
+                if let Some(dates) = original_dates {
+                    *protocol_ver.dates_mut() = dates;
+                }
+                protocol_ver
+            },
             server_name,
             server_revision,
-            server_lang,
+            server_lang: server_lang.unwrap_or_default(),
             use_password,
             clients_max,
             clients_on,
             spectators_on,
-            map_name,
+            map_name: map_name.unwrap_or_default(),
             map_width,
             map_height,
             map_set,
@@ -301,8 +814,8 @@ mod tests {
                     max_spectators: 10,
                 },
                 V3Data {
-                    game_date: DateTime::from_utc(NaiveDateTime::from_timestamp(715875, 0), Utc),
-                    start_date: DateTime::from_utc(NaiveDateTime::from_timestamp(715875, 0), Utc),
+                    game_date: OpenTTDDate(715875),
+                    start_date: OpenTTDDate(715875),
                 },
                 V4Data {
                     active_newgrf: hashmap! {
@@ -318,17 +831,236 @@ mod tests {
             clients_max: 25,
             use_password: false,
             server_revision: CString::new("1.5.3").unwrap(),
-            server_lang: 22,
+            server_lang: NetworkLanguage::Russian,
             spectators_on: 0,
             map_width: 1024,
             map_height: 1024,
-            map_set: 1,
+            map_set: Landscape::SubArctic,
             dedicated: true,
         };
 
         (b, srv)
     }
 
+    fn fixtures_v7() -> (Vec<u8>, ServerResponse) {
+        let b = hex!(
+            "
+            07452301000000000001FFFFFFFF0001444E0700
+            48B3F9E4FD0DF2A72B5F44D3C8A2F4A04F70656E
+            4746580063EC0A0063EC0A000F000A5465737420
+            5365727665720031342E31000019000000040004
+            0101
+        "
+        )
+        .to_vec();
+
+        let srv = ServerResponse {
+            protocol_ver: ProtocolVer::V7(
+                V2Data {
+                    max_companies: 15,
+                    current_companies: 0,
+                    max_spectators: 10,
+                },
+                V3Data {
+                    game_date: OpenTTDDate(715875),
+                    start_date: OpenTTDDate(715875),
+                },
+                V4Data {
+                    active_newgrf: hashmap! {
+                        0x00074e44 => NewGRFHash(hex!("48b3f9e4fd0df2a72b5f44d3c8a2f4a0")),
+                    },
+                },
+                V5Data {
+                    gamescript_version: -1,
+                    gamescript_name: CString::default(),
+                },
+                V6Data {
+                    newgrf_serialisation: NewGRFSerialisation::GrfIdMd5Name(hashmap! {
+                        0x00074e44 => CString::new("OpenGFX").unwrap(),
+                    }),
+                },
+                V7Data {
+                    ticks_playing: 0x12345,
+                },
+            ),
+            server_name: CString::new("Test Server").unwrap(),
+            map_name: CString::default(),
+            clients_on: 0,
+            clients_max: 25,
+            use_password: false,
+            server_revision: CString::new("14.1").unwrap(),
+            server_lang: NetworkLanguage::Any,
+            spectators_on: 0,
+            map_width: 1024,
+            map_height: 1024,
+            map_set: Landscape::SubArctic,
+            dedicated: true,
+        };
+
+        (b, srv)
+    }
+
+    fn fixtures_v2() -> (Vec<u8>, ServerResponse) {
+        let b = hex!(
+            "
+            020F010A546573742053657276657200
+            302E362E300001000A01008732CE2A52
+            616E646F6D204D617000000100010001
+        "
+        )
+        .to_vec();
+
+        let srv = ServerResponse {
+            protocol_ver: ProtocolVer::V2(
+                V2Data {
+                    max_companies: 15,
+                    current_companies: 1,
+                    max_spectators: 10,
+                },
+                V3Data {
+                    game_date: OpenTTDDate::from_ymd(1955, 6, 1).unwrap(),
+                    start_date: OpenTTDDate::from_ymd(1950, 1, 1).unwrap(),
+                },
+            ),
+            server_name: CString::new("Test Server").unwrap(),
+            map_name: CString::new("Random Map").unwrap(),
+            clients_on: 1,
+            clients_max: 10,
+            use_password: false,
+            server_revision: CString::new("0.6.0").unwrap(),
+            server_lang: NetworkLanguage::English,
+            spectators_on: 0,
+            map_width: 256,
+            map_height: 256,
+            map_set: Landscape::Temperate,
+            dedicated: true,
+        };
+
+        (b, srv)
+    }
+
+    fn fixtures_v5() -> (Vec<u8>, ServerResponse) {
+        let b = hex!(
+            "
+            05030000004275737920426565000144
+            4E070048B3F9E4FD0DF2A72B5F44D3C8
+            A2F4A063EC0A0063EC0A000F010A5465
+            73742053657276657200312E31302E33
+            00020119020052616E646F6D204D6170
+            00000100020000
+        "
+        )
+        .to_vec();
+
+        let srv = ServerResponse {
+            protocol_ver: ProtocolVer::V5(
+                V2Data {
+                    max_companies: 15,
+                    current_companies: 1,
+                    max_spectators: 10,
+                },
+                V3Data {
+                    game_date: OpenTTDDate(715875),
+                    start_date: OpenTTDDate(715875),
+                },
+                V4Data {
+                    active_newgrf: hashmap! {
+                        0x00074e44 => NewGRFHash(hex!("48b3f9e4fd0df2a72b5f44d3c8a2f4a0")),
+                    },
+                },
+                V5Data {
+                    gamescript_version: 3,
+                    gamescript_name: CString::new("Busy Bee").unwrap(),
+                },
+            ),
+            server_name: CString::new("Test Server").unwrap(),
+            map_name: CString::new("Random Map").unwrap(),
+            clients_on: 2,
+            clients_max: 25,
+            use_password: true,
+            server_revision: CString::new("1.10.3").unwrap(),
+            server_lang: NetworkLanguage::German,
+            spectators_on: 0,
+            map_width: 256,
+            map_height: 512,
+            map_set: Landscape::Temperate,
+            dedicated: false,
+        };
+
+        (b, srv)
+    }
+
+    /// As sent by OpenTTD 12
+    fn fixtures_v6() -> (Vec<u8>, ServerResponse) {
+        let b = hex!(
+            "
+            0600FFFFFFFF0001444E070048B3F9E4
+            FD0DF2A72B5F44D3C8A2F4A063EC0A00
+            63EC0A000F000A546573742053657276
+            65720031322E32000019000000040004
+            0101
+        "
+        )
+        .to_vec();
+
+        let srv = ServerResponse {
+            protocol_ver: ProtocolVer::V6(
+                V2Data {
+                    max_companies: 15,
+                    current_companies: 0,
+                    max_spectators: 10,
+                },
+                V3Data {
+                    game_date: OpenTTDDate(715875),
+                    start_date: OpenTTDDate(715875),
+                },
+                V4Data {
+                    active_newgrf: hashmap! {
+                        0x00074e44 => NewGRFHash(hex!("48b3f9e4fd0df2a72b5f44d3c8a2f4a0")),
+                    },
+                },
+                V5Data::default(),
+                V6Data {
+                    newgrf_serialisation: NewGRFSerialisation::GrfIdMd5,
+                },
+            ),
+            server_name: CString::new("Test Server").unwrap(),
+            map_name: CString::default(),
+            clients_on: 0,
+            clients_max: 25,
+            use_password: false,
+            server_revision: CString::new("12.2").unwrap(),
+            server_lang: NetworkLanguage::Any,
+            spectators_on: 0,
+            map_width: 1024,
+            map_height: 1024,
+            map_set: Landscape::SubArctic,
+            dedicated: true,
+        };
+
+        (b, srv)
+    }
+
+    fn fixtures_lookup_id() -> (Vec<u8>, ServerResponse) {
+        let b = hex!(
+            "
+            0602FFFFFFFF00020100000005000000
+            63EC0A0063EC0A000F000A5465737420
+            5365727665720031322E320000190000
+            000400040101
+        "
+        )
+        .to_vec();
+
+        let (_, mut srv) = fixtures_v6();
+        if let ProtocolVer::V6(_, _, ref mut v4data, _, ref mut v6data) = srv.protocol_ver {
+            v4data.active_newgrf.clear();
+            v6data.newgrf_serialisation = NewGRFSerialisation::LookupId(vec![1, 5]);
+        }
+
+        (b, srv)
+    }
+
     #[test]
     fn test_parse_server_response() {
         let (input, expectation) = fixtures();
@@ -347,4 +1079,92 @@ mod tests {
 
         assert_eq!(expectation, result);
     }
+
+    #[test]
+    fn test_parse_server_response_v7() {
+        let (input, expectation) = fixtures_v7();
+
+        let result = parse_server_response(&input).unwrap();
+
+        assert_eq!(expectation, result.1);
+    }
+
+    #[test]
+    fn test_write_server_response_v7() {
+        let (expectation, input) = fixtures_v7();
+
+        let mut result = Vec::new();
+        input.write_pkt(&mut result).unwrap();
+
+        assert_eq!(expectation, result);
+    }
+
+    #[test]
+    fn test_write_server_response_limits() {
+        let (_, mut input) = fixtures();
+        input.server_name = CString::new("Ж".repeat(50)).unwrap();
+
+        assert!(input.write_pkt(&mut Vec::new()).is_err());
+
+        input.truncate_to_limits();
+        assert_eq!(input.server_name.as_bytes().len(), 78);
+
+        let mut result = Vec::new();
+        input.write_pkt(&mut result).unwrap();
+        assert_eq!(parse_server_response(&result).unwrap().1, input);
+    }
+
+    #[test]
+    fn test_server_response_v2() {
+        let (input, expectation) = fixtures_v2();
+
+        assert_eq!(
+            parse_server_response(&input).unwrap(),
+            (&[][..], expectation.clone())
+        );
+
+        let mut result = Vec::new();
+        expectation.write_pkt(&mut result).unwrap();
+        assert_eq!(result, input);
+
+        let mut late = expectation;
+        late.protocol_ver.dates_mut().game_date = OpenTTDDate::from_ymd(2100, 1, 1).unwrap();
+        assert!(late.write_pkt(&mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_server_response_v5_v6() {
+        for (bytes, srv) in [fixtures_v5(), fixtures_v6(), fixtures_lookup_id()] {
+            assert_eq!(
+                parse_server_response(&bytes).unwrap(),
+                (&[][..], srv.clone())
+            );
+
+            let mut result = Vec::new();
+            srv.write_pkt(&mut result).unwrap();
+            assert_eq!(result, bytes);
+        }
+    }
+
+    #[test]
+    fn test_newgrf_serialisation_errors() {
+        let (mut input, _) = fixtures_v6();
+        input[1] = 3;
+        assert_eq!(
+            parse_server_response(&input).unwrap_err(),
+            nom::Err::Failure(Error::UnknownType {
+                kind: "NewGRF serialisation",
+                value: 3
+            })
+        );
+
+        let (_, mut srv) = fixtures_v6();
+        if let ProtocolVer::V6(_, _, _, _, ref mut v6data) = srv.protocol_ver {
+            v6data.newgrf_serialisation = NewGRFSerialisation::LookupId(vec![1]);
+        }
+        assert_eq!(
+            srv.write_pkt(&mut Vec::new()).unwrap_err().kind(),
+            std::io::ErrorKind::InvalidData
+        );
+    }
 }
@@ -0,0 +1,513 @@
+use crate::date::*;
+use crate::limits::*;
+use crate::network_language::*;
+use crate::server_response::*;
+
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::fmt;
+
+/// Invariant violated by the data passed to `ServerResponseBuilder`
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValidationError {
+    UnsupportedVersion(u8),
+    TooManyClients {
+        clients_on: u8,
+        clients_max: u8,
+    },
+    TooManySpectators {
+        spectators_on: u8,
+        clients_on: u8,
+    },
+    TooManyCompanies {
+        current_companies: u8,
+        max_companies: u8,
+    },
+    CompanyLimitExceeded(u8),
+    /// Date outside 1920 to mid-2099 for game info versions 1 and 2
+    DateOutOfRange(OpenTTDDate),
+    TooManyNewGRFs(usize),
+    StringTooLong {
+        field: &'static str,
+        len: usize,
+        limit: usize,
+    },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        use self::ValidationError::*;
+
+        match *self {
+            UnsupportedVersion(v) => write!(fmt, "unsupported game info version {}", v),
+            TooManyClients {
+                clients_on,
+                clients_max,
+            } => write!(
+                fmt,
+                "{} clients on, but at most {} allowed",
+                clients_on, clients_max
+            ),
+            TooManySpectators {
+                spectators_on,
+                clients_on,
+            } => write!(
+                fmt,
+                "{} spectators, but only {} clients on",
+                spectators_on, clients_on
+            ),
+            TooManyCompanies {
+                current_companies,
+                max_companies,
+            } => write!(
+                fmt,
+                "{} companies, but at most {} allowed",
+                current_companies, max_companies
+            ),
+            CompanyLimitExceeded(v) => write!(
+                fmt,
+                "company limit {} exceeds OpenTTD maximum of {}",
+                v, MAX_COMPANIES
+            ),
+            DateOutOfRange(v) => {
+                write!(fmt, "date {} cannot be sent before game info version 3", v)
+            }
+            TooManyNewGRFs(v) => write!(
+                fmt,
+                "{} NewGRFs, but at most {} can be sent",
+                v, NETWORK_MAX_GRF_COUNT
+            ),
+            StringTooLong { field, len, limit } => {
+                write!(fmt, "{}", string_too_long_message(field, len, limit))
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Builds a `ServerResponse` for the newest game info version unless told otherwise
+#[derive(Clone, Debug, PartialEq)]
+pub struct ServerResponseBuilder {
+    version: u8,
+    server_name: CString,
+    server_revision: CString,
+    server_lang: NetworkLanguage,
+    use_password: bool,
+    clients_max: u8,
+    clients_on: Option<u8>,
+    spectators_on: u8,
+    map_name: CString,
+    map_width: u16,
+    map_height: u16,
+    map_set: Landscape,
+    dedicated: bool,
+    max_companies: u8,
+    current_companies: u8,
+    max_spectators: u8,
+    game_date: OpenTTDDate,
+    start_date: OpenTTDDate,
+    newgrfs: HashMap<u32, NewGRFHash>,
+    newgrf_names: Option<HashMap<u32, CString>>,
+    gamescript_version: i32,
+    gamescript_name: CString,
+    ticks_playing: u64,
+}
+
+impl Default for ServerResponseBuilder {
+    fn default() -> Self {
+        let start_date = OpenTTDDate::from_ymd(1950, 1, 1).unwrap();
+
+        Self {
+            version: NETWORK_GAME_INFO_VERSION,
+            server_name: CString::default(),
+            server_revision: CString::default(),
+            server_lang: NetworkLanguage::Any,
+            use_password: false,
+            clients_max: 25,
+            clients_on: None,
+            spectators_on: 0,
+            map_name: CString::default(),
+            map_width: 256,
+            map_height: 256,
+            map_set: Landscape::Temperate,
+            dedicated: true,
+            max_companies: MAX_COMPANIES as u8,
+            current_companies: 0,
+            max_spectators: 15,
+            game_date: start_date,
+            start_date,
+            newgrfs: HashMap::new(),
+            newgrf_names: None,
+            gamescript_version: -1,
+            gamescript_name: CString::default(),
+            ticks_playing: 0,
+        }
+    }
+}
+
+impl ServerResponse {
+    pub fn builder() -> ServerResponseBuilder {
+        ServerResponseBuilder::default()
+    }
+}
+
+impl ServerResponseBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Game info version to encode, defaults to `NETWORK_GAME_INFO_VERSION`
+    pub fn version(mut self, version: u8) -> Self {
+        self.version = version;
+        self
+    }
+
+    pub fn server_name(mut self, v: CString) -> Self {
+        self.server_name = v;
+        self
+    }
+
+    pub fn server_revision(mut self, v: CString) -> Self {
+        self.server_revision = v;
+        self
+    }
+
+    pub fn server_lang(mut self, v: NetworkLanguage) -> Self {
+        self.server_lang = v;
+        self
+    }
+
+    pub fn use_password(mut self, v: bool) -> Self {
+        self.use_password = v;
+        self
+    }
+
+    pub fn clients_max(mut self, v: u8) -> Self {
+        self.clients_max = v;
+        self
+    }
+
+    /// Number of connected clients, spectators included. Derived from `spectators_on` if not set.
+    pub fn clients_on(mut self, v: u8) -> Self {
+        self.clients_on = Some(v);
+        self
+    }
+
+    pub fn spectators_on(mut self, v: u8) -> Self {
+        self.spectators_on = v;
+        self
+    }
+
+    pub fn map_name(mut self, v: CString) -> Self {
+        self.map_name = v;
+        self
+    }
+
+    pub fn map_size(mut self, width: u16, height: u16) -> Self {
+        self.map_width = width;
+        self.map_height = height;
+        self
+    }
+
+    pub fn map_set(mut self, v: Landscape) -> Self {
+        self.map_set = v;
+        self
+    }
+
+    pub fn dedicated(mut self, v: bool) -> Self {
+        self.dedicated = v;
+        self
+    }
+
+    pub fn max_companies(mut self, v: u8) -> Self {
+        self.max_companies = v;
+        self
+    }
+
+    pub fn current_companies(mut self, v: u8) -> Self {
+        self.current_companies = v;
+        self
+    }
+
+    pub fn max_spectators(mut self, v: u8) -> Self {
+        self.max_spectators = v;
+        self
+    }
+
+    pub fn game_date(mut self, v: OpenTTDDate) -> Self {
+        self.game_date = v;
+        self
+    }
+
+    pub fn start_date(mut self, v: OpenTTDDate) -> Self {
+        self.start_date = v;
+        self
+    }
+
+    pub fn newgrf(mut self, id: u32, hash: NewGRFHash) -> Self {
+        self.newgrfs.insert(id, hash);
+        self
+    }
+
+    /// Add a NewGRF along with its name. Names are sent since game info version 6.
+    pub fn named_newgrf(mut self, id: u32, hash: NewGRFHash, name: CString) -> Self {
+        self.newgrfs.insert(id, hash);
+        self.newgrf_names
+            .get_or_insert_with(HashMap::new)
+            .insert(id, name);
+        self
+    }
+
+    pub fn gamescript(mut self, version: i32, name: CString) -> Self {
+        self.gamescript_version = version;
+        self.gamescript_name = name;
+        self
+    }
+
+    pub fn ticks_playing(mut self, v: u64) -> Self {
+        self.ticks_playing = v;
+        self
+    }
+
+    fn validate(&self, clients_on: u8) -> Vec<ValidationError> {
+        let mut errors = vec![];
+
+        if check_game_info_version(self.version).is_err() {
+            errors.push(ValidationError::UnsupportedVersion(self.version));
+        }
+        if clients_on > self.clients_max {
+            errors.push(ValidationError::TooManyClients {
+                clients_on,
+                clients_max: self.clients_max,
+            });
+        }
+        if self.spectators_on > clients_on {
+            errors.push(ValidationError::TooManySpectators {
+                spectators_on: self.spectators_on,
+                clients_on,
+            });
+        }
+        if self.current_companies > self.max_companies {
+            errors.push(ValidationError::TooManyCompanies {
+                current_companies: self.current_companies,
+                max_companies: self.max_companies,
+            });
+        }
+        if self.max_companies as usize > MAX_COMPANIES {
+            errors.push(ValidationError::CompanyLimitExceeded(self.max_companies));
+        }
+        if self.version < 3 {
+            for date in [self.game_date, self.start_date].iter() {
+                if date.to_original_days().is_none() {
+                    errors.push(ValidationError::DateOutOfRange(*date));
+                }
+            }
+        }
+        if self.newgrfs.len() > NETWORK_MAX_GRF_COUNT {
+            errors.push(ValidationError::TooManyNewGRFs(self.newgrfs.len()));
+        }
+
+        let mut strings = vec![
+            ("server name", &self.server_name, NETWORK_NAME_LENGTH),
+            (
+                "server revision",
+                &self.server_revision,
+                NETWORK_REVISION_LENGTH,
+            ),
+            ("map name", &self.map_name, NETWORK_NAME_LENGTH),
+            (
+                "GameScript name",
+                &self.gamescript_name,
+                NETWORK_NAME_LENGTH,
+            ),
+        ];
+        if let Some(ref names) = self.newgrf_names {
+            strings.extend(
+                names
+                    .values()
+                    .map(|name| ("NewGRF name", name, NETWORK_GRF_NAME_LENGTH)),
+            );
+        }
+        for (field, s, limit) in strings {
+            if let Err((field, len, limit)) = check_cstring_len(s, limit, field) {
+                errors.push(ValidationError::StringTooLong { field, len, limit });
+            }
+        }
+
+        errors
+    }
+
+    /// Validate the data and build the response, or return every violated invariant
+    pub fn build(self) -> Result<ServerResponse, Vec<ValidationError>> {
+        let clients_on = self.clients_on.unwrap_or(self.spectators_on);
+        let errors = self.validate(clients_on);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let v2 = V2Data {
+            max_companies: self.max_companies,
+            current_companies: self.current_companies,
+            max_spectators: self.max_spectators,
+        };
+        let v3 = V3Data {
+            game_date: self.game_date,
+            start_date: self.start_date,
+        };
+        let v4 = V4Data {
+            active_newgrf: self.newgrfs,
+        };
+        let v5 = V5Data {
+            gamescript_version: self.gamescript_version,
+            gamescript_name: self.gamescript_name,
+        };
+        let v6 = V6Data {
+            newgrf_serialisation: match self.newgrf_names {
+                Some(names) => NewGRFSerialisation::GrfIdMd5Name(names),
+                None => NewGRFSerialisation::GrfIdMd5,
+            },
+        };
+        let v7 = V7Data {
+            ticks_playing: self.ticks_playing,
+        };
+
+        let version = self.version;
+        let protocol_ver = ProtocolVer::from_parts(version, v2, v3, v4, v5, v6, v7)
+            .map_err(|_| vec![ValidationError::UnsupportedVersion(version)])?;
+
+        let mut response = ServerResponse {
+            protocol_ver,
+            server_name: self.server_name,
+            server_revision: self.server_revision,
+            server_lang: self.server_lang,
+            use_password: self.use_password,
+            clients_max: self.clients_max,
+            clients_on,
+            spectators_on: self.spectators_on,
+            map_name: self.map_name,
+            map_width: self.map_width,
+            map_height: self.map_height,
+            map_set: self.map_set,
+            dedicated: self.dedicated,
+        };
+        response.take_unsent_lang_and_map_name();
+
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_server_response() {
+        let srv = ServerResponse::builder()
+            .server_name(CString::new("Test Server").unwrap())
+            .server_revision(CString::new("14.1").unwrap())
+            .spectators_on(2)
+            .build()
+            .unwrap();
+
+        assert_eq!(u8::from(&srv.protocol_ver), NETWORK_GAME_INFO_VERSION);
+        assert_eq!(srv.clients_on, 2);
+
+        let errors = ServerResponse::builder()
+            .version(9)
+            .clients_max(10)
+            .clients_on(11)
+            .max_companies(16)
+            .current_companies(17)
+            .build()
+            .unwrap_err();
+
+        assert_eq!(
+            errors,
+            vec![
+                ValidationError::UnsupportedVersion(9),
+                ValidationError::TooManyClients {
+                    clients_on: 11,
+                    clients_max: 10
+                },
+                ValidationError::TooManyCompanies {
+                    current_companies: 17,
+                    max_companies: 16
+                },
+                ValidationError::CompanyLimitExceeded(16),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_validates_strings() {
+        let errors = ServerResponse::builder()
+            .server_name(CString::new("x".repeat(80)).unwrap())
+            .clients_on(1)
+            .spectators_on(2)
+            .named_newgrf(
+                1,
+                NewGRFHash([0; 16]),
+                CString::new("y".repeat(NETWORK_GRF_NAME_LENGTH)).unwrap(),
+            )
+            .build()
+            .unwrap_err();
+
+        assert_eq!(
+            errors,
+            vec![
+                ValidationError::TooManySpectators {
+                    spectators_on: 2,
+                    clients_on: 1
+                },
+                ValidationError::StringTooLong {
+                    field: "server name",
+                    len: 80,
+                    limit: NETWORK_NAME_LENGTH
+                },
+                ValidationError::StringTooLong {
+                    field: "NewGRF name",
+                    len: 80,
+                    limit: NETWORK_GRF_NAME_LENGTH
+                },
+            ]
+        );
+        assert_eq!(
+            errors[1].to_string(),
+            "server name is 80 bytes long, OpenTTD allows at most 79"
+        );
+    }
+
+    #[test]
+    fn test_build_named_newgrfs() {
+        let srv = ServerResponse::builder()
+            .named_newgrf(1, NewGRFHash([1; 16]), CString::new("OpenGFX").unwrap())
+            .build()
+            .unwrap();
+
+        match srv.protocol_ver {
+            ProtocolVer::V7(_, _, v4, _, v6, _) => {
+                assert_eq!(v4.active_newgrf, hashmap! { 1 => NewGRFHash([1; 16]) });
+                assert_eq!(
+                    v6.newgrf_serialisation,
+                    NewGRFSerialisation::GrfIdMd5Name(
+                        hashmap! { 1 => CString::new("OpenGFX").unwrap() }
+                    )
+                );
+            }
+            ref other => panic!("unexpected protocol version {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_build_validates_dates() {
+        let early = OpenTTDDate::from_ymd(1919, 12, 31).unwrap();
+        let builder = ServerResponse::builder().start_date(early);
+
+        assert_eq!(
+            builder.clone().version(2).build().unwrap_err(),
+            vec![ValidationError::DateOutOfRange(early)]
+        );
+        assert!(builder.version(3).build().is_ok());
+    }
+}
@@ -1,8 +1,8 @@
+use crate::error::*;
 use crate::util::*;
 
 use byteorder::{LittleEndian, WriteBytesExt};
 use nom::{self, number::complete::*, *};
-use std;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct ServerUnregisterData {
@@ -20,7 +20,7 @@ impl ByteWriter for ServerUnregisterData {
 }
 
 impl ServerUnregisterData {
-    named!(pub from_bytes<&[u8], Self>,
+    named!(pub from_bytes<&[u8], Self, Error>,
         do_parse!(
             master_server_version: le_u8 >>
             port: le_u16 >>
@@ -0,0 +1,88 @@
+use crate::master_response_list::SEND_MTU;
+use crate::server_detail_info::*;
+use crate::server_response::*;
+use crate::Packet;
+
+use std::ffi::CString;
+use std::net::{SocketAddr, UdpSocket};
+use std::thread;
+use std::time::Duration;
+
+/// Loopback stand-in for a game server, answering `ClientFindServer` and `ClientDetailInfo`
+/// until no request arrived for two seconds
+pub(crate) struct StandIn {
+    addr: SocketAddr,
+    name: String,
+    ignore: usize,
+    garbage: bool,
+}
+
+impl StandIn {
+    pub(crate) fn new(name: &str) -> Self {
+        Self {
+            addr: SocketAddr::from(([127, 0, 0, 1], 0)),
+            name: name.to_string(),
+            ignore: 0,
+            garbage: false,
+        }
+    }
+
+    /// Address to listen on, defaults to an ephemeral port on 127.0.0.1
+    pub(crate) fn bind(mut self, addr: SocketAddr) -> Self {
+        self.addr = addr;
+        self
+    }
+
+    /// Leave the first `n` requests unanswered to force retries
+    pub(crate) fn ignore(mut self, n: usize) -> Self {
+        self.ignore = n;
+        self
+    }
+
+    /// Answer ignored requests with an unparsable packet instead of staying silent
+    pub(crate) fn garbage(mut self) -> Self {
+        self.garbage = true;
+        self
+    }
+
+    /// Start answering in a background thread, returning the address listened on
+    pub(crate) fn spawn(self) -> SocketAddr {
+        let socket = UdpSocket::bind(self.addr).unwrap();
+        socket
+            .set_read_timeout(Some(Duration::from_secs(2)))
+            .unwrap();
+        let addr = socket.local_addr().unwrap();
+        let response = ServerResponse::builder()
+            .server_name(CString::new(self.name.as_str()).unwrap())
+            .build()
+            .unwrap();
+
+        let (ignore, garbage) = (self.ignore, self.garbage);
+        thread::spawn(move || {
+            let mut buf = [0; SEND_MTU];
+            let mut seen = 0;
+            while let Ok((len, from)) = socket.recv_from(&mut buf) {
+                seen += 1;
+                if seen <= ignore {
+                    if garbage {
+                        socket.send_to(&[3, 0, 0xFF], from).unwrap();
+                    }
+                    continue;
+                }
+
+                let reply = match Packet::from_incoming_bytes(&buf[..len]) {
+                    Ok(Packet::ClientFindServer) => Packet::ServerResponse(response.clone()),
+                    Ok(Packet::ClientDetailInfo) => Packet::ServerDetailInfo(ServerDetailInfo {
+                        company_info_version: NETWORK_COMPANY_INFO_VERSION,
+                        companies: vec![],
+                        spectators: vec![],
+                    }),
+                    _ => continue,
+                };
+                socket.send_to(&reply.to_bytes().unwrap(), from).unwrap();
+            }
+        });
+
+        addr
+    }
+}
@@ -0,0 +1,262 @@
+use crate::error::*;
+use crate::server_detail_info::{ClientInfo, CompanyInfo};
+use crate::server_newgrfs::NewGRFInfo;
+use crate::server_register::ServerRegistrationData;
+use crate::server_response::{ServerResponse, V5Data};
+
+use std::borrow::Cow;
+use std::ffi::CStr;
+use std::fmt;
+
+/// First code point OpenTTD reserves for string control codes
+pub const SCC_CONTROL_START: char = '\u{E000}';
+/// Last code point OpenTTD reserves for string control codes
+pub const SCC_CONTROL_END: char = '\u{E1FF}';
+/// First code point OpenTTD uses to draw sprites inline in text
+pub const SCC_SPRITE_START: char = '\u{E200}';
+/// Last code point OpenTTD uses to draw sprites inline in text
+pub const SCC_SPRITE_END: char = '\u{E2FF}';
+/// Code point of the first colour code, U+E000 plus the TTD control byte 0x88
+const SCC_BLUE: u32 = 0xE088;
+
+/// Text colour selected by a string control code
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TextColour {
+    Blue,
+    Silver,
+    Gold,
+    Red,
+    Purple,
+    LightBrown,
+    Orange,
+    Green,
+    Yellow,
+    DarkGreen,
+    Cream,
+    Brown,
+    White,
+    LightBlue,
+    Grey,
+    DarkBlue,
+    Black,
+}
+
+/// Colours in control code order
+const COLOURS: [(TextColour, &str); 17] = [
+    (TextColour::Blue, "blue"),
+    (TextColour::Silver, "silver"),
+    (TextColour::Gold, "gold"),
+    (TextColour::Red, "red"),
+    (TextColour::Purple, "purple"),
+    (TextColour::LightBrown, "light brown"),
+    (TextColour::Orange, "orange"),
+    (TextColour::Green, "green"),
+    (TextColour::Yellow, "yellow"),
+    (TextColour::DarkGreen, "dark green"),
+    (TextColour::Cream, "cream"),
+    (TextColour::Brown, "brown"),
+    (TextColour::White, "white"),
+    (TextColour::LightBlue, "light blue"),
+    (TextColour::Grey, "grey"),
+    (TextColour::DarkBlue, "dark blue"),
+    (TextColour::Black, "black"),
+];
+
+impl TextColour {
+    /// Colour selected by a control code, `None` for other characters
+    pub fn from_control_code(c: char) -> Option<Self> {
+        (c as u32)
+            .checked_sub(SCC_BLUE)
+            .and_then(|index| COLOURS.get(index as usize))
+            .map(|entry| entry.0)
+    }
+
+    /// Control code selecting this colour
+    pub fn control_code(&self) -> char {
+        let index = COLOURS.iter().position(|entry| entry.0 == *self).unwrap();
+        std::char::from_u32(SCC_BLUE + index as u32).unwrap()
+    }
+
+    pub fn name(&self) -> &'static str {
+        COLOURS.iter().find(|entry| entry.0 == *self).unwrap().1
+    }
+}
+
+impl fmt::Display for TextColour {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "{}", self.name())
+    }
+}
+
+/// Run of text drawn in a single colour, `None` until the first colour code
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextSpan {
+    pub colour: Option<TextColour>,
+    pub text: String,
+}
+
+/// Check whether OpenTTD would refuse to print the character: ASCII control characters other
+/// than newline, and the code points reserved for string control codes and inline sprites
+pub fn is_control_code(c: char) -> bool {
+    (c < ' ' && c != '\n')
+        || (SCC_CONTROL_START..=SCC_CONTROL_END).contains(&c)
+        || (SCC_SPRITE_START..=SCC_SPRITE_END).contains(&c)
+}
+
+/// Remove control codes, keeping only printable text
+pub fn strip_control_codes(s: &str) -> String {
+    s.chars().filter(|&c| !is_control_code(c)).collect()
+}
+
+/// Split text at its colour codes. Other control codes are dropped.
+pub fn colour_spans(s: &str) -> Vec<TextSpan> {
+    let mut spans = vec![];
+    let mut current = TextSpan {
+        colour: None,
+        text: String::new(),
+    };
+
+    for c in s.chars() {
+        if let Some(colour) = TextColour::from_control_code(c) {
+            let next = TextSpan {
+                colour: Some(colour),
+                text: String::new(),
+            };
+            let done = std::mem::replace(&mut current, next);
+            if !done.text.is_empty() {
+                spans.push(done);
+            }
+        } else if !is_control_code(c) {
+            current.text.push(c);
+        }
+    }
+    if !current.text.is_empty() {
+        spans.push(current);
+    }
+
+    spans
+}
+
+/// Text helpers for the NUL-terminated strings in packets, e.g. `ServerResponse::server_name`,
+/// `CompanyInfo::name` or `ServerRegistrationData::welcome_message`
+pub trait NetworkText {
+    /// Text as UTF-8, failing with `Error::InvalidString` if it is not valid
+    fn text(&self) -> Result<&str, Error>;
+
+    /// Text as UTF-8, with invalid sequences replaced by U+FFFD
+    fn text_lossy(&self) -> Cow<'_, str>;
+
+    /// Printable text without control codes, e.g. for logs or terminals
+    fn plain_text(&self) -> String {
+        strip_control_codes(&self.text_lossy())
+    }
+
+    /// Text split at its colour codes
+    fn colour_spans(&self) -> Vec<TextSpan> {
+        colour_spans(&self.text_lossy())
+    }
+}
+
+impl NetworkText for CStr {
+    fn text(&self) -> Result<&str, Error> {
+        self.to_str().map_err(|_| Error::InvalidString)
+    }
+
+    fn text_lossy(&self) -> Cow<'_, str> {
+        self.to_string_lossy()
+    }
+}
+
+/// Add `<field>_text` and `plain_<field>` accessors for string fields of packet models
+macro_rules! text_accessors {
+    ($ty:ty { $($field:ident => $text:ident, $plain:ident;)* }) => {
+        impl $ty {
+            $(
+                #[doc = concat!("`", stringify!($field), "` as UTF-8, see `NetworkText::text`")]
+                pub fn $text(&self) -> Result<&str, Error> {
+                    self.$field.text()
+                }
+
+                #[doc = concat!("`", stringify!($field), "` without control codes, see `NetworkText::plain_text`")]
+                pub fn $plain(&self) -> String {
+                    self.$field.plain_text()
+                }
+            )*
+        }
+    };
+}
+
+text_accessors!(ServerResponse {
+    server_name => server_name_text, plain_server_name;
+    server_revision => server_revision_text, plain_server_revision;
+    map_name => map_name_text, plain_map_name;
+});
+text_accessors!(V5Data {
+    gamescript_name => gamescript_name_text, plain_gamescript_name;
+});
+text_accessors!(CompanyInfo {
+    name => name_text, plain_name;
+});
+text_accessors!(ClientInfo {
+    name => name_text, plain_name;
+});
+text_accessors!(ServerRegistrationData {
+    welcome_message => welcome_message_text, plain_welcome_message;
+});
+text_accessors!(NewGRFInfo {
+    name => name_text, plain_name;
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    #[test]
+    fn test_network_text() {
+        let name = CString::new("\u{E08B}Red \u{E002}Server\u{E094} Club\t").unwrap();
+        assert_eq!(name.plain_text(), "Red Server Club");
+        assert_eq!(
+            name.colour_spans(),
+            vec![
+                TextSpan {
+                    colour: Some(TextColour::Red),
+                    text: "Red Server".to_string(),
+                },
+                TextSpan {
+                    colour: Some(TextColour::White),
+                    text: " Club".to_string(),
+                },
+            ]
+        );
+        assert_eq!(TextColour::Red.control_code(), '\u{E08B}');
+
+        let invalid = CString::new(vec![b'a', 0xFF, b'b']).unwrap();
+        assert_eq!(invalid.text(), Err(Error::InvalidString));
+        assert_eq!(invalid.text_lossy(), "a\u{FFFD}b");
+
+        assert_eq!(strip_control_codes("\u{E200}Train\u{E2FF}"), "Train");
+    }
+
+    #[test]
+    fn test_packet_text_accessors() {
+        let srv = ServerResponse::builder()
+            .server_name(CString::new("\u{E08B}Red Server").unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(srv.server_name_text(), Ok("\u{E08B}Red Server"));
+        assert_eq!(srv.plain_server_name(), "Red Server");
+
+        let registration = ServerRegistrationData {
+            welcome_message: CString::new(vec![b'O', b'K', 0xFF]).unwrap(),
+            server_version: 2,
+            port: 3979,
+            session_key: 0,
+        };
+        assert_eq!(
+            registration.welcome_message_text(),
+            Err(Error::InvalidString)
+        );
+        assert_eq!(registration.plain_welcome_message(), "OK\u{FFFD}");
+    }
+}
@@ -1,15 +1,59 @@
-use chrono::prelude::*;
-use std;
+use crate::error::*;
+
+use nom::number::complete::le_u8;
 use std::ffi::CString;
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use std::time::Instant;
+
+/// Read a NUL-terminated string
+pub fn read_cstring(input: &[u8]) -> ParseResult<'_, CString> {
+    match input.iter().position(|&v| v == 0) {
+        // The slice ends before the first NUL, so `CString::new` cannot fail
+        Some(pos) => Ok((&input[pos + 1..], CString::new(&input[..pos]).unwrap())),
+        None => Err(nom::Err::Failure(Error::Truncated)),
+    }
+}
+
+/// Read a `u8` type tag and convert it, failing with `Error::UnknownType` on unexpected values
+pub fn typed_u8<'a, T>(
+    input: &'a [u8],
+    kind: &'static str,
+    from_num: fn(u8) -> Option<T>,
+) -> ParseResult<'a, T> {
+    let (rest, value) = le_u8(input)?;
+    match from_num(value) {
+        Some(v) => Ok((rest, v)),
+        None => Err(nom::Err::Failure(Error::UnknownType { kind, value })),
+    }
+}
 
-named!(pub read_cstring<&[u8], CString>, do_parse!(
-    s: map_res!(take_till!(|v| v == 0), |arr| CString::new(arr)) >>
-    take!(1) >>
-    (s)
-));
+/// Wait for a datagram until `deadline`, returning `None` once it passes. Errors some platforms
+/// report for ICMP port unreachable from earlier sends also return `None`, so callers should
+/// check the deadline themselves before giving up.
+pub(crate) fn recv_until(
+    socket: &UdpSocket,
+    buf: &mut [u8],
+    deadline: Instant,
+) -> io::Result<Option<(usize, SocketAddr)>> {
+    let wait = deadline.saturating_duration_since(Instant::now());
+    if wait.is_zero() {
+        return Ok(None);
+    }
+    socket.set_read_timeout(Some(wait))?;
 
-pub fn datetime_from_ts<T: Into<i64>>(ts: T) -> DateTime<Utc> {
-    DateTime::from_utc(NaiveDateTime::from_timestamp(ts.into(), 0), Utc)
+    match socket.recv_from(buf) {
+        Ok(v) => Ok(Some(v)),
+        Err(ref e)
+            if e.kind() == io::ErrorKind::WouldBlock
+                || e.kind() == io::ErrorKind::TimedOut
+                || e.kind() == io::ErrorKind::ConnectionReset
+                || e.kind() == io::ErrorKind::ConnectionRefused =>
+        {
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
 }
 
 pub trait ByteWriter {